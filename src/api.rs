@@ -1,15 +1,28 @@
 use crate::{
-  database,
+  auth::Admin,
+  chapters, database, export, fonts, formatter,
   model::{
+    artifact::Artifact,
     constant::*,
     task::Status::{Fail, Finish, Processing},
     *,
   },
+  range::RangedFile,
+  signer,
+  subs::{self, load_subtitles},
+  translate::{self, Translator},
   utils::*,
+  version::{IfMatch, Tagged},
+};
+use rocket::{
+  form::Form,
+  get,
+  http::{ContentType, Status},
+  post,
+  serde::json::Json,
+  State,
 };
-use rocket::{form::Form, fs::NamedFile, get, http::Status, post, serde::json::Json, State};
 use serde_json::{json, Value};
-use std::collections::HashMap;
 use tokio::sync::mpsc;
 
 #[post("/api/gen", data = "<data>")]
@@ -19,6 +32,22 @@ pub async fn gen_video(
 ) -> Result<Json<Value>, Status> {
   log::info!("Generating video");
 
+  if data.remove_bg
+    && data.background == avatar::BackgroundMode::Image
+    && data.background_image.is_none()
+  {
+    log::warn!("Background image required");
+    return Err(Status::UnprocessableEntity);
+  }
+  if data.audio.is_some() && data.script.is_some() {
+    log::warn!("Both narration audio and script provided");
+    return Err(Status::UnprocessableEntity);
+  }
+  if let Err(e) = check_source(&data) {
+    log::warn!("{}", e);
+    return Err(Status::UnprocessableEntity);
+  }
+
   // gen random code
   let mut code = generate_rand_code();
   while let Some(true) = database::check_code_exists(&code).ok() {
@@ -27,37 +56,152 @@ pub async fn gen_video(
   log::debug!("Generated code : {}", code);
 
   create_code_dir(&code).map_err(|_| Status::InternalServerError)?;
-  let video_path = create_file(&code, VIDEO_FILE).map_err(|_| Status::InternalServerError)?;
   let avatar_path = create_file(&code, AVATAR_FILE).map_err(|_| Status::InternalServerError)?;
 
-  handle(data.video.persist_to(video_path).await, "Persisting video")
-    .map_err(|_| Status::InternalServerError)?;
+  let mut slideshow = None;
+  if let Some(video) = data.video.as_mut() {
+    let video_path = create_file(&code, VIDEO_FILE).map_err(|_| Status::InternalServerError)?;
+    handle(video.persist_to(video_path).await, "Persisting video")
+      .map_err(|_| Status::InternalServerError)?;
+  } else {
+    slideshow = Some(persist_slides(&code, &mut data).await?);
+  }
 
   handle(
     data.avatar.persist_to(avatar_path).await,
     "Persisting avatar",
   )
   .map_err(|_| Status::InternalServerError)?;
+
+  if let Some(audio) = data.audio.as_mut() {
+    let narration =
+      video::narration_file(audio.content_type()).ok_or(Status::UnprocessableEntity)?;
+    let narration_path = create_file(&code, narration).map_err(|_| Status::InternalServerError)?;
+    handle(
+      audio.persist_to(narration_path).await,
+      "Persisting narration",
+    )
+    .map_err(|_| Status::InternalServerError)?;
+  }
+
+  let script = match data.script.as_deref() {
+    Some(script) => {
+      let script_path = create_file(&code, SCRIPT_FILE).map_err(|_| Status::InternalServerError)?;
+      handle(std::fs::write(script_path, script), "Persisting script")
+        .map_err(|_| Status::InternalServerError)?;
+      Some(script::parse_script(script).map_err(|_| Status::UnprocessableEntity)?)
+    }
+    None => None,
+  };
+
+  let background = data.background_settings();
+  if let (Some(background_image), Some(image)) =
+    (data.background_image.as_mut(), background.image.as_deref())
+  {
+    let background_path = create_file(&code, image).map_err(|_| Status::InternalServerError)?;
+    handle(
+      background_image.persist_to(background_path).await,
+      "Persisting background image",
+    )
+    .map_err(|_| Status::InternalServerError)?;
+  }
   log::debug!("data={:?}", data);
 
+  // 檢查頭像時間軸是否超過影片長度，投影片只有在指定秒數時才能得知長度
+  if !data.timeline.is_empty() {
+    let checked = match &slideshow {
+      None => check_timeline_duration(&code, &data.timeline),
+      Some(slideshow) => avatar::check_timeline(&data.timeline, slideshow.total_duration())
+        .map_err(|e| {
+          log::warn!("Invalid timeline for code: {}: {}", code, e);
+          Status::UnprocessableEntity
+        }),
+    };
+    if let Err(status) = checked {
+      let _ = delete_code_dir(&code);
+      return Err(status);
+    }
+  }
+
   // 新增任務至資料庫
   handle(
-    database::insert_task(&code, data.subtitle),
+    database::insert_task(&code, data.subtitle, data.keep_files),
     &format!("Inserting task for code: {}", code),
   )
   .map_err(|_| Status::InternalServerError)?;
 
+  handle(
+    database::update_task_timeline(&code, &data.timeline),
+    &format!("Updating task timeline for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+
+  handle(
+    database::update_task_generation_options(&code, &data.generation_options()),
+    &format!("Updating task generation options for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+
+  handle(
+    database::update_task_encoding_options(&code, &data.encoding_options()),
+    &format!("Updating task encoding options for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+
+  handle(
+    database::update_task_audio_options(&code, &data.audio_processing),
+    &format!("Updating task audio options for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+
+  handle(
+    database::update_task_transcription_options(&code, &data.transcription),
+    &format!("Updating task transcription options for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+
+  handle(
+    database::update_task_background_options(&code, &background),
+    &format!("Updating task background options for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+
+  handle(
+    database::update_task_karaoke(&code, data.karaoke),
+    &format!("Updating task karaoke for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+
   // send request to gen worker
   let request = worker::GenVideoRequest {
     code: code.clone(),
-    x: data.x,
-    y: data.y,
-    shape: data.shape.clone(),
+    layout: data.layout.clone(),
+    narration: data.audio.is_some(),
+    script,
+    slideshow,
     remove_bg: data.remove_bg,
+    background,
+    audio: data.audio_processing.clone(),
+    preview: match data.preview {
+      true => Some(data.preview_seconds),
+      false => None,
+    },
     subtitle: data.subtitle,
+    transcription: data.transcription.clone(),
+    subtitle_format: data.subtitle_format.clone(),
+    chapters: data.chapters.clone(),
   };
   log::debug!("request={:?}", request);
 
+  // 保存預覽任務的請求，之後可轉為完整生成
+  if request.preview.is_some() {
+    handle(
+      database::update_task_render_request(&code, Some(&request)),
+      &format!("Updating task render request for code: {}", code),
+    )
+    .map_err(|_| Status::InternalServerError)?;
+  }
+
   let tx = &sender.gen_sender;
   match tx.try_send(request) {
     Ok(_) => {
@@ -90,6 +234,364 @@ pub async fn set_email(code: &str, data: Form<email::Request>) -> Result<(), Sta
   Ok(())
 }
 
+#[post("/api/gen/<code>/rerender", data = "<data>")]
+pub async fn rerender(
+  sender: &State<worker::Sender>,
+  code: &str,
+  data: Form<avatar::Layout>,
+) -> Result<(), Status> {
+  log::info!("Rerendering video for code: {}", code);
+
+  let task = match database::check_code_exists(code) {
+    Ok(true) => handle(
+      database::get_task_info(code),
+      &format!("Getting task info for code: {}", code),
+    )
+    .map_err(|_| Status::InternalServerError)?,
+    Ok(false) => return Err(Status::NotFound),
+    Err(_) => return Err(Status::InternalServerError),
+  };
+  log::debug!("task={:?}", task);
+
+  // 需要保留模式且任務已結束才能重新合成
+  if let Processing = task.status {
+    log::warn!("Task is still processing for code: {}", code);
+    return Err(Status::Conflict);
+  }
+  if !task.keep_files
+    || get_file_path(code, VIDEO_FILE).is_err()
+    || get_file_path(code, AVATAR_VIDEO_FILE).is_err()
+  {
+    log::warn!("Intermediate files not kept for code: {}", code);
+    return Err(Status::Conflict);
+  }
+
+  handle(
+    database::update_task_status(code, Processing),
+    &format!("Updating task status for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+  handle(
+    database::update_video_status(code, Processing),
+    &format!("Updating video status for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+
+  // send request to rerender worker
+  let request = worker::RerenderRequest {
+    code: code.to_string(),
+    layout: data.into_inner(),
+  };
+  log::debug!("request={:?}", request);
+
+  let tx = &sender.rerender_sender;
+  let status = match tx.try_send(request) {
+    Ok(_) => {
+      log::info!("Rerender request sent for code: {}", code);
+      return Ok(());
+    }
+    Err(e) => match e {
+      mpsc::error::TrySendError::Full(_) => Status::ServiceUnavailable,
+      mpsc::error::TrySendError::Closed(_) => Status::InternalServerError,
+    },
+  };
+
+  // 未能排入佇列時還原狀態，避免任務一直停在Processing
+  log::warn!("Failed to send rerender request for code: {}", code);
+  handle(
+    database::update_task_status(code, task.status),
+    &format!("Restoring task status for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+  handle(
+    database::update_video_status(code, task.video_status),
+    &format!("Restoring video status for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+  Err(status)
+}
+
+#[post("/api/gen/<code>/render")]
+pub async fn render(sender: &State<worker::Sender>, code: &str) -> Result<(), Status> {
+  log::info!("Promoting preview to full render for code: {}", code);
+
+  let task = match database::check_code_exists(code) {
+    Ok(true) => handle(
+      database::get_task_info(code),
+      &format!("Getting task info for code: {}", code),
+    )
+    .map_err(|_| Status::InternalServerError)?,
+    Ok(false) => return Err(Status::NotFound),
+    Err(_) => return Err(Status::InternalServerError),
+  };
+  log::debug!("task={:?}", task);
+
+  if let Processing = task.status {
+    log::warn!("Task is still processing for code: {}", code);
+    return Err(Status::Conflict);
+  }
+  // 只有保留原始檔案的預覽任務才能轉為完整生成
+  let preview = handle(
+    database::get_render_request(code),
+    &format!("Getting render request for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+  let preview = match preview {
+    Some(preview) if get_file_path(code, SOURCE_VIDEO_FILE).is_ok() => preview,
+    _ => {
+      log::warn!("Task is not a preview for code: {}", code);
+      return Err(Status::Conflict);
+    }
+  };
+
+  handle(
+    database::update_task_status(code, Processing),
+    &format!("Updating task status for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+  handle(
+    database::update_video_status(code, Processing),
+    &format!("Updating video status for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+  handle(
+    database::update_task_render_request(code, None),
+    &format!("Updating task render request for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+
+  // send request to gen worker
+  let request = worker::GenVideoRequest {
+    preview: None,
+    ..preview.clone()
+  };
+  log::debug!("request={:?}", request);
+
+  let tx = &sender.gen_sender;
+  let status = match tx.try_send(request) {
+    Ok(_) => {
+      log::info!("Video generation request sent for code: {}", code);
+      return Ok(());
+    }
+    Err(e) => match e {
+      mpsc::error::TrySendError::Full(_) => Status::ServiceUnavailable,
+      mpsc::error::TrySendError::Closed(_) => Status::InternalServerError,
+    },
+  };
+
+  // 未能排入佇列時還原狀態，任務仍為預覽
+  log::warn!("Failed to send video generation request for code: {}", code);
+  handle(
+    database::update_task_status(code, task.status),
+    &format!("Restoring task status for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+  handle(
+    database::update_video_status(code, task.video_status),
+    &format!("Restoring video status for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+  handle(
+    database::update_task_render_request(code, Some(&preview)),
+    &format!("Restoring task render request for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+  Err(status)
+}
+
+#[get("/api/gen/<code>/timeline")]
+pub async fn get_timeline(code: &str) -> Result<Json<Vec<avatar::Keyframe>>, Status> {
+  log::info!("Getting timeline for code: {}", code);
+
+  match database::check_code_exists(code) {
+    Ok(true) => {}
+    Ok(false) => return Err(Status::NotFound),
+    Err(_) => return Err(Status::InternalServerError),
+  }
+
+  let timeline = handle(
+    database::get_timeline(code),
+    &format!("Getting timeline for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+
+  Ok(Json(timeline))
+}
+
+#[post("/api/gen/<code>/timeline", data = "<data>")]
+pub async fn set_timeline(code: &str, data: Form<avatar::TimelineRequest>) -> Result<(), Status> {
+  log::info!("Setting timeline for code: {}", code);
+
+  match database::check_code_exists(code) {
+    Ok(true) => {}
+    Ok(false) => return Err(Status::NotFound),
+    Err(_) => return Err(Status::InternalServerError),
+  }
+
+  // 影片已被刪除時無法再套用時間軸
+  if get_file_path(code, VIDEO_FILE).is_err() {
+    log::warn!("Video not kept for code: {}", code);
+    return Err(Status::Conflict);
+  }
+  check_timeline_duration(code, &data.timeline)?;
+
+  handle(
+    database::update_task_timeline(code, &data.timeline),
+    &format!("Updating task timeline for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+
+  Ok(())
+}
+
+#[get("/api/gen/<code>/chapters")]
+pub async fn get_chapters(code: &str) -> Result<Json<Vec<chapter::Chapter>>, Status> {
+  log::info!("Getting chapters for code: {}", code);
+
+  match database::check_code_exists(code) {
+    Ok(true) => {}
+    Ok(false) => return Err(Status::NotFound),
+    Err(_) => return Err(Status::InternalServerError),
+  }
+
+  let chapters = handle(
+    database::get_chapters(code),
+    &format!("Getting chapters for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+
+  Ok(Json(chapters))
+}
+
+// 編輯後的章節重新合成寫入影片
+#[post("/api/gen/<code>/chapters", data = "<data>")]
+pub async fn set_chapters(
+  sender: &State<worker::Sender>,
+  code: &str,
+  data: Form<chapter::Request>,
+) -> Result<(), Status> {
+  log::info!("Setting chapters for code: {}", code);
+
+  match database::check_code_exists(code) {
+    Ok(true) => {}
+    Ok(false) => return Err(Status::NotFound),
+    Err(_) => return Err(Status::InternalServerError),
+  }
+
+  // 原影片還在時檢查是否超過影片長度
+  if let Ok(video_path) = get_file_path(code, VIDEO_FILE) {
+    let duration = handle(
+      get_video_duration(&video_path),
+      &format!("Getting video duration for code: {}", code),
+    )
+    .map_err(|_| Status::UnprocessableEntity)?;
+    chapter::check_chapters(&data.chapters, Some(duration)).map_err(|e| {
+      log::warn!("Invalid chapters for code: {}: {}", code, e);
+      Status::UnprocessableEntity
+    })?;
+  }
+
+  handle(
+    chapters::save_chapters(code, &data.chapters),
+    &format!("Saving chapters for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+
+  // send request to merge worker
+  let request = worker::MergeSubsRequest {
+    code: code.to_string(),
+  };
+  handle(
+    sender.merge_sender.try_send(request),
+    "Sending request to merge worker",
+  )
+  .map_err(|_| Status::ServiceUnavailable)?;
+
+  log::info!("Merge request sent for code: {}", code);
+  Ok(())
+}
+
+// 影片來源需為影片、投影片圖片或PDF其中之一
+fn check_source(data: &video::Request<'_>) -> Result<(), String> {
+  let sources = [
+    data.video.is_some(),
+    !data.slides.is_empty(),
+    data.pdf.is_some(),
+  ];
+  if sources.iter().filter(|source| **source).count() != 1 {
+    return Err(String::from("Exactly one of video, slides or pdf required"));
+  }
+  if data.video.is_some() {
+    return Ok(());
+  }
+
+  // 投影片沒有音訊，需要上傳旁白或旁白稿
+  if data.audio.is_none() && data.script.is_none() {
+    return Err(String::from(
+      "Narration audio or script required for slideshow",
+    ));
+  }
+  if !data.slide_durations.is_empty() && !data.slide_timestamps.is_empty() {
+    return Err(String::from("Both slide durations and timestamps provided"));
+  }
+  // PDF的頁數要在轉換後才能檢查
+  if !data.slides.is_empty() {
+    for timing in [&data.slide_durations, &data.slide_timestamps] {
+      if !timing.is_empty() && timing.len() != data.slides.len() {
+        return Err(String::from("Slide timing count does not match slides"));
+      }
+    }
+  }
+  Ok(())
+}
+
+async fn persist_slides(
+  code: &str,
+  data: &mut video::Request<'_>,
+) -> Result<slideshow::Slideshow, Status> {
+  create_dir(code, SLIDES_DIR).map_err(|_| Status::InternalServerError)?;
+
+  let mut slides = Vec::new();
+  for (i, slide) in data.slides.iter_mut().enumerate() {
+    let extension = match slide.content_type() {
+      Some(content_type) if content_type == &rocket::http::ContentType::PNG => "png",
+      _ => "jpg",
+    };
+    let filename = format!("{}/slide_{:03}.{}", SLIDES_DIR, i, extension);
+    let slide_path = create_file(code, &filename).map_err(|_| Status::InternalServerError)?;
+    handle(slide.persist_to(slide_path).await, "Persisting slide")
+      .map_err(|_| Status::InternalServerError)?;
+    slides.push(filename);
+  }
+
+  if let Some(pdf) = data.pdf.as_mut() {
+    let pdf_path = create_file(code, SLIDES_PDF_FILE).map_err(|_| Status::InternalServerError)?;
+    handle(pdf.persist_to(pdf_path).await, "Persisting pdf")
+      .map_err(|_| Status::InternalServerError)?;
+  }
+
+  Ok(slideshow::Slideshow {
+    pdf: data.pdf.is_some(),
+    slides,
+    durations: data.slide_durations.clone(),
+    timestamps: data.slide_timestamps.clone(),
+  })
+}
+
+fn check_timeline_duration(code: &str, timeline: &[avatar::Keyframe]) -> Result<(), Status> {
+  let video_path = get_file_path(code, VIDEO_FILE).map_err(|_| Status::InternalServerError)?;
+  let duration = handle(
+    get_video_duration(&video_path),
+    &format!("Getting video duration for code: {}", code),
+  )
+  .map_err(|_| Status::UnprocessableEntity)?;
+
+  avatar::check_timeline(timeline, Some(duration)).map_err(|e| {
+    log::warn!("Invalid timeline for code: {}: {}", code, e);
+    Status::UnprocessableEntity
+  })
+}
+
 #[get("/api/gen/<code>")]
 pub async fn check_task_status(code: &str) -> Result<(), Status> {
   log::info!("Checking task status for code: {}", code);
@@ -113,80 +615,450 @@ pub async fn check_task_status(code: &str) -> Result<(), Status> {
   }
 }
 
-#[get("/download/<code>")]
-pub async fn download(code: &str) -> Result<rocket::fs::NamedFile, Status> {
+#[post("/api/gen/<code>/link", data = "<data>")]
+pub async fn create_download_link(
+  code: &str,
+  data: Form<link::Request>,
+) -> Result<Json<Value>, Status> {
+  log::info!("Creating download link for code: {}", code);
+
+  match database::check_code_exists(code) {
+    Ok(true) => {}
+    Ok(false) => return Err(Status::NotFound),
+    Err(_) => return Err(Status::InternalServerError),
+  }
+
+  let link = handle(
+    signer::sign_download(code, &data.resource, data.ttl, data.single_use),
+    &format!("Signing download link for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+
+  let response = json!({
+    "url": link.to_url(),
+    "query": link.query(),
+    "resource": link.resource,
+    "expires": link.expires,
+  });
+  Ok(Json(response))
+}
+
+#[get("/download/<code>?<link..>")]
+pub async fn download(
+  code: &str,
+  link: link::Signature,
+  client: link::Client,
+) -> Result<RangedFile, Status> {
   log::info!("Download file for code: {}", code);
 
-  if let Ok(file_path) = get_file_path(code, RESULT_WITH_SUBS_FILE) {
-    log::info!("Result with subtitles file found for code: {}", code);
+  // 有字幕版本時下載字幕版本，個別檔案請使用artifacts API
+  let artifact = match get_file_path(code, RESULT_WITH_SUBS_FILE) {
+    Ok(_) => Artifact::ResultWithSubtitles,
+    Err(_) => Artifact::Result,
+  };
+  log::info!("Serving artifact '{}' for code: {}", artifact.name(), code);
 
-    return handle(
-      NamedFile::open(file_path).await,
-      &format!("Opening result with subtitles file for code: {}", code),
-    )
-    .map_err(|_| Status::InternalServerError);
-  }
+  verify_link(code, DOWNLOAD_RESOURCE, &link)?;
+  serve_artifact(code, artifact, &link, &client).await
+}
 
-  if let Ok(file_path) = get_file_path(code, RESULT_FILE) {
-    log::info!("Result file found for code: {}", code);
+#[get("/api/tasks/<code>/artifacts")]
+pub async fn list_artifacts(code: &str) -> Result<Json<Value>, Status> {
+  log::info!("Listing artifacts for code: {}", code);
 
-    return handle(
-      NamedFile::open(file_path).await,
-      &format!("Opening result file for code: {}", code),
-    )
-    .map_err(|_| Status::InternalServerError);
-  } else {
-    log::warn!("File not found for code: {}", code);
-    Err(Status::NotFound)
+  match database::check_code_exists(code) {
+    Ok(true) => {}
+    Ok(false) => return Err(Status::NotFound),
+    Err(_) => return Err(Status::InternalServerError),
   }
+
+  let artifacts = handle(
+    export::list_artifacts(code),
+    &format!("Listing artifacts for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+
+  let artifacts: Vec<Value> = artifacts
+    .iter()
+    .map(|(artifact, size)| {
+      json!({
+        "name": artifact.name(),
+        "file": artifact.file(),
+        "size": size,
+        "path": format!("/api/tasks/{}/artifacts/{}", code, artifact.name()),
+      })
+    })
+    .collect();
+
+  let response = json!({
+    "code": code,
+    "artifacts": artifacts,
+  });
+  Ok(Json(response))
 }
 
-#[get("/api/gen/subtitle/<code>")]
-pub async fn gen_subtitle(code: &str) -> Result<(), Status> {
-  log::info!("Generating subtitle for code: {}", &code);
+// 只能有一個trailing參數，簽章欄位個別取出
+#[get("/api/tasks/<code>/transcript?<expires>&<nonce>&<signature>&<query..>")]
+pub async fn get_transcript(
+  code: &str,
+  expires: Option<i64>,
+  nonce: Option<String>,
+  signature: Option<String>,
+  query: transcript::Query,
+  client: link::Client,
+) -> Result<(ContentType, String), Status> {
+  log::info!("Getting transcript for code: {}", code);
 
-  let mut map = HashMap::new();
-  map.insert(
-    "file_path",
-    handle(get_file_path(code, AUDIO_FILE), "Inserting file_path")
-      .map_err(|_| Status::InternalServerError)?,
-  );
-  map.insert(
-    "save_path",
-    handle(create_file(code, SUBS_FILE), "Inserting save_path")
-      .map_err(|_| Status::InternalServerError)?,
-  );
+  // 與transcript檔案共用簽章資源
+  let link = link::Signature {
+    expires,
+    nonce,
+    signature,
+  };
+  verify_link(code, Artifact::Transcript.name(), &link)?;
+  let subtitles = stored_subtitles(code)?;
+  let document = subs::to_transcript_document(&subtitles, &query);
+  consume_link(code, &link, &client, false)?;
+
+  let content_type = match query.format {
+    transcript::Format::Txt => ContentType::Plain,
+    transcript::Format::Md => {
+      ContentType::new("text", "markdown").with_params(("charset", "utf-8"))
+    }
+  };
+  Ok((content_type, document))
+}
 
-  let response = handle(
-    make_request("http://localhost:5000/gen_subtitle", &map).await,
-    "Making request",
+#[get("/api/tasks/<code>/artifacts/<artifact>?<link..>")]
+pub async fn get_artifact(
+  code: &str,
+  artifact: Artifact,
+  link: link::Signature,
+  client: link::Client,
+) -> Result<RangedFile, Status> {
+  log::info!("Getting artifact '{}' for code: {}", artifact.name(), code);
+
+  verify_link(code, artifact.name(), &link)?;
+  serve_artifact(code, artifact, &link, &client).await
+}
+
+#[get("/api/tasks/<code>/bundle.zip?<link..>")]
+pub async fn download_bundle(
+  code: &str,
+  link: link::Signature,
+  client: link::Client,
+) -> Result<RangedFile, Status> {
+  log::info!("Downloading bundle for code: {}", code);
+
+  verify_link(code, BUNDLE_RESOURCE, &link)?;
+
+  let file_path = match handle(
+    export::build_bundle(code),
+    &format!("Building bundle for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?
+  {
+    Some(file_path) => file_path,
+    None => return Err(Status::NotFound),
+  };
+
+  let file = handle(
+    RangedFile::open(file_path).await,
+    &format!("Opening bundle for code: {}", code),
   )
   .map_err(|_| Status::InternalServerError)?;
 
-  if response.status().is_success() {
-    log::info!("Python gen subtitle success");
-    Ok(())
-  } else {
-    Err(Status::InternalServerError)
+  consume_link(code, &link, &client, is_continuation(&file, &client))?;
+  Ok(file)
+}
+
+async fn serve_artifact(
+  code: &str,
+  artifact: Artifact,
+  link: &link::Signature,
+  client: &link::Client,
+) -> Result<RangedFile, Status> {
+  let file_path = match handle(
+    export::prepare_artifact(code, artifact),
+    &format!(
+      "Preparing artifact '{}' for code: {}",
+      artifact.name(),
+      code
+    ),
+  )
+  .map_err(|_| Status::InternalServerError)?
+  {
+    Some(file_path) => file_path,
+    None => {
+      log::warn!(
+        "Artifact '{}' not found for code: {}",
+        artifact.name(),
+        code
+      );
+      return Err(Status::NotFound);
+    }
+  };
+
+  let file = handle(
+    RangedFile::open(file_path).await,
+    &format!("Opening artifact '{}' for code: {}", artifact.name(), code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+
+  consume_link(code, link, client, is_continuation(&file, client))?;
+  Ok(file)
+}
+
+// 簽章包含資源名稱，連結只能下載簽發時指定的資源
+fn verify_link(code: &str, resource: &str, link: &link::Signature) -> Result<(), Status> {
+  match (link.expires, &link.signature) {
+    (Some(expires), Some(signature)) => {
+      signer::verify_download(code, resource, expires, link.nonce.as_deref(), signature)
+        .map_err(|_| Status::Forbidden)
+    }
+    (_, _) => {
+      log::warn!("Unsigned download request for code: {}", code);
+      Err(Status::Forbidden)
+    }
   }
 }
 
+// bytes=0-等從頭開始的Range等同重新下載，不算續傳
+fn is_continuation(file: &RangedFile, client: &link::Client) -> bool {
+  file.is_continuation(client.range.as_deref(), client.if_range.as_deref())
+}
+
+// 單次連結在確認檔案存在後才標記為已使用
+fn consume_link(
+  code: &str,
+  link: &link::Signature,
+  client: &link::Client,
+  continuation: bool,
+) -> Result<(), Status> {
+  match &link.nonce {
+    Some(nonce) => signer::consume_nonce(code, nonce, client.ip.as_deref(), continuation)
+      .map_err(|_| Status::Forbidden),
+    None => Ok(()),
+  }
+}
+
+#[get("/api/subtitle/<code>")]
+pub async fn get_subtitle(code: &str) -> Result<Tagged<Json<Value>>, Status> {
+  log::info!("Getting subtitle for code: {}", code);
+
+  let subtitles = stored_subtitles(code)?;
+
+  let revision = handle(
+    database::get_latest_revision(code),
+    &format!("Getting latest revision for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?
+  .unwrap_or(revision::ORIGINAL_REVISION);
+
+  // 自動斷行後超過閱讀速度的字幕
+  let warnings = handle(
+    database::get_reading_speed_warnings(code),
+    &format!("Getting reading speed warnings for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+
+  Ok(Tagged {
+    inner: Json(json!({
+      "subtitles": subtitles,
+      "warnings": warnings,
+    })),
+    revision,
+  })
+}
+
 #[post("/api/set/subtitle/<code>", data = "<data>")]
 pub async fn set_subtitle(
   sender: &State<worker::Sender>,
   code: &str,
+  if_match: IfMatch,
   data: Form<subtitle::Request>,
-) -> Result<(), Status> {
+) -> Result<Tagged<()>, Status> {
   log::info!("Setting subtitle for code: {}", code);
 
-  let subs = &data.subtitles;
+  let expected = if_match.revision()?;
+  let revision = save_subtitles(
+    sender,
+    code,
+    &data.subtitles,
+    data.author.as_deref(),
+    expected,
+  )?;
+  Ok(Tagged {
+    inner: (),
+    revision,
+  })
+}
+
+#[post("/api/subtitle/<code>/shift", data = "<data>")]
+pub async fn shift_subtitle(
+  sender: &State<worker::Sender>,
+  code: &str,
+  if_match: IfMatch,
+  data: Form<subtitle::ShiftRequest>,
+) -> Result<Tagged<Json<Vec<subtitle::Subtitle>>>, Status> {
+  log::info!("Shifting subtitle by {}ms for code: {}", data.offset, code);
+
+  let expected = if_match.revision()?;
+  let mut subtitles = stored_subtitles(code)?;
+  let time = |time: &str| subs::parse_time(time).ok_or(Status::UnprocessableEntity);
+  let from = data.from.as_deref().map(time).transpose()?;
+  let to = data.to.as_deref().map(time).transpose()?;
+  subs::shift_subtitles(&mut subtitles, data.offset, from, to).map_err(|e| {
+    log::warn!("Invalid subtitles for code: {}: {}", code, e);
+    Status::UnprocessableEntity
+  })?;
+  // 全部字幕都被移出時間軸時不儲存
+  if subtitles.is_empty() {
+    log::warn!("No subtitles left after shifting for code: {}", code);
+    return Err(Status::UnprocessableEntity);
+  }
+
+  let revision = save_subtitles(sender, code, &subtitles, data.author.as_deref(), expected)?;
+  Ok(Tagged {
+    inner: Json(subtitles),
+    revision,
+  })
+}
+
+#[post("/api/subtitle/<code>/scale", data = "<data>")]
+pub async fn scale_subtitle(
+  sender: &State<worker::Sender>,
+  code: &str,
+  if_match: IfMatch,
+  data: Form<subtitle::ScaleRequest>,
+) -> Result<Tagged<Json<Vec<subtitle::Subtitle>>>, Status> {
+  log::info!("Scaling subtitle for code: {}", code);
+
+  let expected = if_match.revision()?;
+  let mut subtitles = stored_subtitles(code)?;
+  let time = |time: &str| subs::parse_time(time).ok_or(Status::UnprocessableEntity);
+  subs::scale_subtitles(
+    &mut subtitles,
+    (time(&data.source_start)?, time(&data.target_start)?),
+    (time(&data.source_end)?, time(&data.target_end)?),
+  )
+  .map_err(|e| {
+    log::warn!("Invalid scale request for code: {}: {}", code, e);
+    Status::UnprocessableEntity
+  })?;
+  if subtitles.is_empty() {
+    log::warn!("No subtitles left after scaling for code: {}", code);
+    return Err(Status::UnprocessableEntity);
+  }
+
+  let revision = save_subtitles(sender, code, &subtitles, data.author.as_deref(), expected)?;
+  Ok(Tagged {
+    inner: Json(subtitles),
+    revision,
+  })
+}
+
+#[get("/api/subtitle/<code>/revisions")]
+pub async fn list_revisions(code: &str) -> Result<Json<Vec<revision::Revision>>, Status> {
+  log::info!("Listing subtitle revisions for code: {}", code);
+
+  stored_subtitles(code)?;
+  let revisions = handle(
+    database::get_revisions(code),
+    &format!("Getting revisions for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+  Ok(Json(revisions))
+}
+
+// 未指定against時與前一版比較
+#[get("/api/subtitle/<code>/revisions/<revision>/diff?<against>")]
+pub async fn diff_revision(
+  code: &str,
+  revision: u32,
+  against: Option<u32>,
+) -> Result<Json<Vec<revision::Change>>, Status> {
+  log::info!("Diffing subtitle revision {} for code: {}", revision, code);
+
+  let against = match against.or(revision.checked_sub(1)) {
+    Some(against) => against,
+    None => return Err(Status::UnprocessableEntity),
+  };
+  let old = revision_subtitles(code, against)?;
+  let new = revision_subtitles(code, revision)?;
+
+  Ok(Json(subs::diff_subtitles(&old, &new)))
+}
+
+// 還原為新的一個版本，不會刪除之後的版本
+#[post("/api/subtitle/<code>/revisions/<revision>/restore", data = "<data>")]
+pub async fn restore_revision(
+  sender: &State<worker::Sender>,
+  code: &str,
+  revision: u32,
+  if_match: IfMatch,
+  data: Form<revision::RestoreRequest>,
+) -> Result<Tagged<Json<Vec<subtitle::Subtitle>>>, Status> {
+  log::info!(
+    "Restoring subtitle revision {} for code: {}",
+    revision,
+    code
+  );
+
+  let expected = if_match.revision()?;
+  stored_subtitles(code)?;
+  let subtitles = revision_subtitles(code, revision)?;
+
+  let revision = save_subtitles(sender, code, &subtitles, data.author.as_deref(), expected)?;
+  Ok(Tagged {
+    inner: Json(subtitles),
+    revision,
+  })
+}
+
+fn revision_subtitles(code: &str, revision: u32) -> Result<Vec<subtitle::Subtitle>, Status> {
+  match database::get_revision_subtitles(code, revision) {
+    Ok(Some(subtitles)) => Ok(subtitles),
+    Ok(None) => Err(Status::NotFound),
+    Err(_) => Err(Status::InternalServerError),
+  }
+}
+
+fn stored_subtitles(code: &str) -> Result<Vec<subtitle::Subtitle>, Status> {
+  match database::check_code_exists(code) {
+    Ok(true) => {}
+    Ok(false) => return Err(Status::NotFound),
+    Err(_) => return Err(Status::InternalServerError),
+  }
+
+  match load_subtitles(code) {
+    Ok(Some(subtitles)) => Ok(subtitles),
+    Ok(None) => Err(Status::NotFound),
+    Err(_) => Err(Status::InternalServerError),
+  }
+}
 
+// 寫入字幕並新增版本後重新燒錄
+fn save_subtitles(
+  sender: &State<worker::Sender>,
+  code: &str,
+  subs: &Vec<subtitle::Subtitle>,
+  author: Option<&str>,
+  expected: Option<u32>,
+) -> Result<u32, Status> {
   handle(
-    database::update_task_subtitles(code, subs),
-    &format!("Updating task subtitles for code: {}", code),
+    subs::record_original_revision(code),
+    &format!("Recording original revision for code: {}", code),
   )
   .map_err(|_| Status::InternalServerError)?;
 
+  let revision = handle(
+    database::update_task_subtitles(code, subs, author.unwrap_or("anonymous"), expected),
+    &format!("Updating task subtitles for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?
+  .ok_or(Status::PreconditionFailed)?;
+
   handle(
     database::update_subtitles_status(code, Finish),
     &format!("Updating subtitles status for code: {}", code),
@@ -204,12 +1076,7 @@ pub async fn set_subtitle(
 
   log::info!("Merge request sent for code: {}", code);
 
-  Ok(())
-}
-
-#[get("/file/<code>/<filename>")]
-pub fn get_file_path_for_code(code: &str, filename: &str) -> Result<String, Status> {
-  get_file_path(code, filename).map_err(|_| Status::NotFound)
+  Ok(revision)
 }
 
 // #[tokio::test]
@@ -248,3 +1115,182 @@ pub fn get_file_path_for_code(code: &str, filename: &str) -> Result<String, Stat
 
 //   assert!(response.is_ok());
 // }
+
+// 預覽斷行後的字幕與閱讀速度警告，不會修改任務的字幕
+#[post("/api/subtitle/<code>/format", data = "<data>")]
+pub async fn format_subtitle(
+  code: &str,
+  data: Form<subtitle::FormatOptions>,
+) -> Result<Json<Value>, Status> {
+  log::info!("Formatting subtitle for code: {}", code);
+
+  let subtitles = stored_subtitles(code)?;
+  let (subtitles, warnings) = formatter::format_subtitles(&subtitles, &data);
+
+  Ok(Json(json!({
+    "subtitles": subtitles,
+    "warnings": warnings,
+  })))
+}
+
+#[get("/api/fonts")]
+pub async fn list_fonts() -> Result<Json<Vec<font::Font>>, Status> {
+  log::info!("Listing fonts");
+
+  let fonts =
+    handle(fonts::list_fonts(), "Listing fonts").map_err(|_| Status::InternalServerError)?;
+  Ok(Json(fonts))
+}
+
+// 以檔名作為字型ID，需為可解析的TTF/OTF/TTC
+#[post("/api/fonts", data = "<data>")]
+pub async fn upload_font(
+  _admin: Admin,
+  mut data: Form<font::Upload<'_>>,
+) -> Result<Json<font::Font>, Status> {
+  log::info!("Uploading font");
+
+  let id = data.file.name().unwrap_or_default().to_string();
+  let ext = data
+    .file
+    .raw_name()
+    .and_then(|name| {
+      std::path::Path::new(name.dangerous_unsafe_unsanitized_raw().as_str())
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+    })
+    .unwrap_or_default();
+  if !fonts::is_valid_id(&id) || !fonts::FONT_EXTENSIONS.contains(&ext.as_str()) {
+    log::warn!("Invalid font file name: {}.{}", id, ext);
+    return Err(Status::UnprocessableEntity);
+  }
+  if fonts::is_registered(&id) {
+    log::warn!("Font '{}' already registered", id);
+    return Err(Status::Conflict);
+  }
+
+  let dir = fonts::font_dir();
+  handle(std::fs::create_dir_all(&dir), "Creating font directory")
+    .map_err(|_| Status::InternalServerError)?;
+  let upload_path = format!("{}/{}.upload", dir, id);
+  handle(data.file.persist_to(&upload_path).await, "Persisting font")
+    .map_err(|_| Status::InternalServerError)?;
+
+  // 確認是有效的字型檔後才加入
+  let names = fonts::read_font_names(std::path::Path::new(&upload_path));
+  let (family, style) = match names {
+    Some(names) => names,
+    None => {
+      log::warn!("Uploaded font '{}' could not be parsed", id);
+      let _ = std::fs::remove_file(&upload_path);
+      return Err(Status::UnprocessableEntity);
+    }
+  };
+  handle(
+    std::fs::rename(&upload_path, format!("{}/{}.{}", dir, id, ext)),
+    "Registering font",
+  )
+  .map_err(|_| Status::InternalServerError)?;
+
+  log::info!("Font '{}' registered", id);
+  Ok(Json(font::Font { id, family, style }))
+}
+
+#[post("/api/subtitle/<code>/translations", data = "<data>")]
+pub async fn translate_subtitle(
+  translator: &State<Box<dyn Translator>>,
+  code: &str,
+  data: Form<translation::Request>,
+) -> Result<Json<Vec<subtitle::Subtitle>>, Status> {
+  log::info!(
+    "Translating subtitle into {} for code: {}",
+    data.target,
+    code
+  );
+
+  let subtitles = stored_subtitles(code)?;
+  let translated = handle(
+    translate::translate_subtitles(
+      translator.as_ref(),
+      &subtitles,
+      data.source.as_deref(),
+      &data.target,
+    )
+    .await,
+    &format!("Translating subtitles for code: {}", code),
+  )
+  .map_err(|_| Status::BadGateway)?;
+
+  handle(
+    database::upsert_subtitle_track(code, &data.target, &translated),
+    &format!("Storing subtitle track for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+
+  Ok(Json(translated))
+}
+
+#[get("/api/subtitle/<code>/translations")]
+pub async fn list_translations(code: &str) -> Result<Json<Vec<translation::Track>>, Status> {
+  log::info!("Listing subtitle translations for code: {}", code);
+
+  match database::check_code_exists(code) {
+    Ok(true) => {}
+    Ok(false) => return Err(Status::NotFound),
+    Err(_) => return Err(Status::InternalServerError),
+  }
+
+  let tracks = handle(
+    database::get_subtitle_tracks(code),
+    &format!("Getting subtitle tracks for code: {}", code),
+  )
+  .map_err(|_| Status::InternalServerError)?;
+  Ok(Json(tracks))
+}
+
+#[get("/api/subtitle/<code>/translations/<language>")]
+pub async fn get_translation(
+  code: &str,
+  language: &str,
+) -> Result<Json<Vec<subtitle::Subtitle>>, Status> {
+  log::info!(
+    "Getting {} subtitle translation for code: {}",
+    language,
+    code
+  );
+
+  match database::get_subtitle_track(code, language) {
+    Ok(Some(subtitles)) => Ok(Json(subtitles)),
+    Ok(None) => Err(Status::NotFound),
+    Err(_) => Err(Status::InternalServerError),
+  }
+}
+
+// 以任務代碼作為權限，只搜尋呼叫者帶入的任務，結果依任務分組並保留相關度順序
+#[get("/api/search?<query..>")]
+pub async fn search_subtitles(query: search::Query) -> Result<Json<Vec<search::TaskHits>>, Status> {
+  log::info!("Searching subtitles in {} tasks", query.code.len());
+
+  let fts_query = match crate::search::fts_query(&query.q) {
+    Some(fts_query) => fts_query,
+    None => return Err(Status::BadRequest),
+  };
+  let hits = handle(
+    database::search_subtitles(&query.code, &fts_query, query.limit, query.offset),
+    "Searching subtitles",
+  )
+  .map_err(|_| Status::InternalServerError)?;
+
+  let mut results: Vec<search::TaskHits> = Vec::new();
+  for (code, hit) in hits {
+    match results.iter_mut().find(|task| task.code == code) {
+      Some(task) => task.matches.push(hit),
+      None => results.push(search::TaskHits {
+        code,
+        matches: vec![hit],
+      }),
+    }
+  }
+  Ok(Json(results))
+}
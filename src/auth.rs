@@ -0,0 +1,48 @@
+use rocket::{
+  http::Status,
+  request::{FromRequest, Outcome, Request},
+};
+use std::env;
+
+// 管理者操作需帶 "Authorization: Bearer <ADMIN_TOKEN>"，未設定ADMIN_TOKEN時一律拒絕
+// 在guard中驗證，未通過時不會讀取上傳的資料
+pub struct Admin;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Admin {
+  type Error = ();
+
+  async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+    let provided = req
+      .headers()
+      .get_one("Authorization")
+      .and_then(|value| value.strip_prefix("Bearer "));
+
+    match check(provided) {
+      Ok(()) => Outcome::Success(Admin),
+      Err(status) => Outcome::Error((status, ())),
+    }
+  }
+}
+
+fn check(provided: Option<&str>) -> Result<(), Status> {
+  let token = match env::var("ADMIN_TOKEN") {
+    Ok(token) if !token.is_empty() => token,
+    _ => {
+      log::warn!("ADMIN_TOKEN not set, rejecting admin request");
+      return Err(Status::Forbidden);
+    }
+  };
+
+  match provided {
+    Some(provided) if constant_time_eq(provided.as_bytes(), token.as_bytes()) => Ok(()),
+    _ => {
+      log::warn!("Invalid admin token");
+      Err(Status::Unauthorized)
+    }
+  }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+  a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,110 @@
+use crate::{
+  database,
+  model::{chapter::Chapter, constant::CHAPTERS_FILE, subtitle::Subtitle},
+  subs::{format_time, parse_time},
+  utils::*,
+};
+use std::fs;
+
+// 以場景切換時間（毫秒）切分章節，標題預設為章節內第一句字幕
+// 與前一個切點或影片結尾距離小於min_duration（毫秒）的切點會被略過
+pub fn build_chapters(
+  scenes: &[u64],
+  duration: u64,
+  min_duration: u64,
+  subtitles: &[Subtitle],
+) -> Vec<Chapter> {
+  let mut scenes: Vec<u64> = scenes
+    .iter()
+    .copied()
+    .filter(|time| *time > 0 && *time < duration)
+    .collect();
+  scenes.sort_unstable();
+
+  let mut bounds = vec![0];
+  for time in scenes {
+    let last = *bounds.last().unwrap_or(&0);
+    if time - last >= min_duration && duration - time >= min_duration {
+      bounds.push(time);
+    }
+  }
+  bounds.push(duration);
+
+  bounds
+    .windows(2)
+    .enumerate()
+    .map(|(i, bound)| Chapter {
+      start_time: format_time(bound[0], ','),
+      end_time: format_time(bound[1], ','),
+      title: chapter_title(subtitles, bound[0], bound[1])
+        .unwrap_or_else(|| format!("Chapter {}", i + 1)),
+    })
+    .collect()
+}
+
+fn chapter_title(subtitles: &[Subtitle], start: u64, end: u64) -> Option<String> {
+  let subtitle = subtitles.iter().find(|subtitle| {
+    parse_time(&subtitle.start_time).is_some_and(|time| time >= start && time < end)
+  })?;
+  let title = subtitle
+    .text
+    .split_whitespace()
+    .collect::<Vec<&str>>()
+    .join(" ");
+  match title.is_empty() {
+    true => None,
+    false => Some(title.chars().take(200).collect()),
+  }
+}
+
+// WebVTT章節檔，播放器以kind="chapters"載入
+pub fn to_vtt(chapters: &[Chapter]) -> String {
+  let cues: String = chapters
+    .iter()
+    .enumerate()
+    .map(|(i, chapter)| {
+      format!(
+        "{}\n{} --> {}\n{}\n\n",
+        i + 1,
+        format_time(parse_time(&chapter.start_time).unwrap_or(0), '.'),
+        format_time(parse_time(&chapter.end_time).unwrap_or(0), '.'),
+        vtt_title(&chapter.title).unwrap_or_else(|| format!("Chapter {}", i + 1))
+      )
+    })
+    .collect();
+  format!("WEBVTT\n\n{}", cues)
+}
+
+// 標題中的換行會結束cue、"-->"會被當成時間行，合併為單行並替換箭頭
+fn vtt_title(title: &str) -> Option<String> {
+  let title = title
+    .split_whitespace()
+    .collect::<Vec<&str>>()
+    .join(" ")
+    .replace("-->", "→");
+  match title.is_empty() {
+    true => None,
+    false => Some(title),
+  }
+}
+
+// 更新章節並重新輸出WebVTT章節檔，沒有章節時移除章節檔
+pub fn save_chapters(code: &str, chapters: &Vec<Chapter>) -> Result<(), Error> {
+  handle(
+    database::update_task_chapters(code, chapters),
+    &format!("Updating task chapters for code: {}", code),
+  )?;
+
+  if chapters.is_empty() {
+    if let Ok(path) = get_file_path(code, CHAPTERS_FILE) {
+      handle(fs::remove_file(&path), &format!("Removing file '{}'", path))?;
+    }
+    return Ok(());
+  }
+  let path = handle(create_file(code, CHAPTERS_FILE), "Creating chapters file")?;
+  handle(
+    fs::write(&path, to_vtt(chapters)),
+    &format!("Writing file '{}'", path),
+  )?;
+  Ok(())
+}
@@ -1,11 +1,37 @@
-use crate::{database, model::constant::*, utils::*};
+use crate::{
+  chapters, database, fonts,
+  model::{
+    audio::AudioOptions,
+    avatar::{Background, BackgroundMode, Layout},
+    chapter::ChapterOptions,
+    constant::*,
+    generation::Preset,
+    slideshow::Slideshow,
+    transcription::TranscriptionOptions,
+  },
+  signer,
+  subs::*,
+  utils::*,
+};
 use lettre::{
   message::header::ContentType, transport::smtp::authentication::Credentials, Message,
   SmtpTransport, Transport,
 };
-use serde_json::Value;
+use serde::Deserialize;
+use serde_json::{json, Value};
 use std::collections::HashMap;
 
+#[derive(Deserialize)]
+struct RenderPdfResponse {
+  slides: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct DetectScenesResponse {
+  // 場景切換時間（秒）
+  timestamps: Vec<f64>,
+}
+
 pub async fn mp4_to_wav(code: &str) -> Result<(), Error> {
   log::info!("Converting MP4 to WAV for code: {}", code);
 
@@ -32,38 +58,74 @@ pub async fn mp4_to_wav(code: &str) -> Result<(), Error> {
   }
 }
 
-pub async fn run_gen_video_python(code: &str, remove_bg: bool) -> Result<(), Error> {
-  log::info!("Running gen video Python script for code: {}", code);
+pub async fn convert_audio_to_wav(code: &str) -> Result<(), Error> {
+  log::info!("Converting narration to WAV for code: {}", code);
 
   let mut map = HashMap::new();
   map.insert(
     "audio_path",
-    handle(get_file_path(code, AUDIO_FILE), "Inserting audio_path")?,
+    handle(get_narration_path(code), "Inserting audio_path")?,
+  );
+  map.insert(
+    "wav_path",
+    handle(create_file(code, AUDIO_FILE), "Inserting wav_path")?,
   );
 
-  if remove_bg {
-    map.insert(
-      "image_path",
-      handle(
-        get_file_path(code, DEBG_AVATAR_FILE),
-        &format!("Inserting image_path({})", DEBG_AVATAR_FILE),
-      )?,
-    );
+  let response = handle(
+    make_request("http://localhost:5000/convert_audio_to_wav", &map).await,
+    "Making request",
+  )?;
+
+  if response.status().is_success() {
+    log::info!("Narration to WAV conversion success");
+    Ok(())
   } else {
-    map.insert(
-      "image_path",
-      handle(
-        get_file_path(code, AVATAR_FILE),
-        &format!("Inserting image_path({})", AVATAR_FILE),
-      )?,
-    );
+    Err(Error::new(ErrorKind::Other, ""))
   }
+}
+
+pub async fn run_gen_video_python(code: &str, image: &str) -> Result<(), Error> {
+  log::info!("Running gen video Python script for code: {}", code);
 
+  let mut map = HashMap::new();
+  map.insert(
+    "audio_path",
+    Value::String(handle(
+      get_file_path(code, AUDIO_FILE),
+      "Inserting audio_path",
+    )?),
+  );
+  map.insert(
+    "image_path",
+    Value::String(handle(
+      get_file_path(code, image),
+      &format!("Inserting image_path({})", image),
+    )?),
+  );
   map.insert(
     "result_dir",
-    handle(create_dir(code, GEN_DIR), "Inserting result_dir")?,
+    Value::String(handle(create_dir(code, GEN_DIR), "Inserting result_dir")?),
   );
 
+  // SadTalker生成參數，使用任務建立時保存的設定
+  let options = handle(
+    database::get_generation_options(code),
+    &format!("Getting generation options for code: {}", code),
+  )?
+  .unwrap_or_else(|| Preset::Standard.generation());
+  map.insert("preprocess", serde_json::to_value(options.preprocess)?);
+  map.insert("still", Value::Bool(options.still_mode));
+  map.insert(
+    "enhancer",
+    match options.face_enhancer {
+      true => Value::String("gfpgan".to_string()),
+      false => Value::Null,
+    },
+  );
+  map.insert("pose_style", Value::from(options.pose_style));
+  map.insert("expression_scale", Value::from(options.expression_scale));
+  map.insert("size", Value::from(options.size));
+
   let response = handle(
     make_request("http://localhost:5000/gen", &map).await,
     "Making request",
@@ -106,32 +168,76 @@ pub async fn merge_avatar_video_chunks(code: &str) -> Result<(), Error> {
   }
 }
 
-pub async fn merge_video_and_avatar_video(
-  code: &str,
-  x: f32,
-  y: f32,
-  shape: &str,
-) -> Result<(), Error> {
+pub async fn merge_video_and_avatar_video(code: &str, layout: &Layout) -> Result<(), Error> {
   log::info!("Merging video and avatar video for code: {}", code);
 
   let mut map = HashMap::new();
   map.insert(
     "main_video_path",
-    handle(get_file_path(code, VIDEO_FILE), "Inserting main_video_path")?,
+    Value::String(handle(
+      get_file_path(code, VIDEO_FILE),
+      "Inserting main_video_path",
+    )?),
   );
   map.insert(
     "avatar_video_path",
-    handle(
+    Value::String(handle(
       get_file_path(code, AVATAR_VIDEO_FILE),
       "Inserting avatar_video_path",
-    )?,
+    )?),
   );
   map.insert(
     "output_path",
-    handle(create_file(code, RESULT_FILE), "Inserting output_path")?,
+    Value::String(handle(
+      create_file(code, RESULT_FILE),
+      "Inserting output_path",
+    )?),
+  );
+  map.insert(
+    "position",
+    Value::String(format!("({},{})", layout.x, layout.y)),
   );
-  map.insert("position", format!("({},{})", x, y));
-  map.insert("avatar_shape", shape.to_string());
+  map.insert("avatar_shape", serde_json::to_value(layout.shape)?);
+  map.insert("anchor", serde_json::to_value(layout.anchor)?);
+  map.insert("scale", serde_json::to_value(layout.scale)?);
+  map.insert(
+    "margin",
+    Value::String(format!("({},{})", layout.margin_x, layout.margin_y)),
+  );
+  map.insert("border_color", Value::String(layout.border_color.clone()));
+  map.insert("border_width", Value::from(layout.border_width));
+
+  // 頭像時間軸，未設定時整段使用固定位置
+  let timeline = handle(
+    database::get_timeline(code),
+    &format!("Getting timeline for code: {}", code),
+  )?;
+  map.insert("timeline", serde_json::to_value(timeline)?);
+
+  // 頭像的背景處理方式，重新合成時沿用生成時的設定
+  let background = handle(
+    database::get_background_options(code),
+    &format!("Getting background options for code: {}", code),
+  )?;
+  map.insert("background", serde_json::to_value(background)?);
+
+  // 有另外上傳旁白、使用TTS或經過音訊後處理時以處理後的音訊取代原影片音訊
+  let processed = handle(
+    database::get_audio_options(code),
+    &format!("Getting audio options for code: {}", code),
+  )?
+  .is_some_and(|options| options.is_enabled());
+  if processed || get_narration_path(code).is_ok() || get_file_path(code, SCRIPT_FILE).is_ok() {
+    map.insert(
+      "audio_path",
+      Value::String(handle(
+        get_file_path(code, AUDIO_FILE),
+        "Inserting audio_path",
+      )?),
+    );
+  }
+  map.insert("chapters", chapter_metadata(code)?);
+  map.insert("encoding", encoding_options(code)?);
 
   let response = handle(
     make_request("http://localhost:5000/merge_video_and_avatar_video", &map).await,
@@ -146,17 +252,38 @@ pub async fn merge_video_and_avatar_video(
   }
 }
 
-pub async fn gen_subtitle(code: &str) -> Result<(), Error> {
+pub async fn gen_subtitle(code: &str, options: &TranscriptionOptions) -> Result<(), Error> {
   log::info!("Generating subtitle for code: {}", &code);
 
   let mut map = HashMap::new();
   map.insert(
     "file_path",
-    handle(get_file_path(code, AUDIO_FILE), "Inserting file_path")?,
+    Value::String(handle(
+      get_file_path(code, AUDIO_FILE),
+      "Inserting file_path",
+    )?),
   );
   map.insert(
     "output_path",
-    handle(create_file(code, SUBS_FILE), "Inserting output_path")?,
+    Value::String(handle(
+      create_file(code, SUBS_FILE),
+      "Inserting output_path",
+    )?),
+  );
+  map.insert("language", serde_json::to_value(&options.language)?);
+  map.insert("model", serde_json::to_value(options.model)?);
+  map.insert("translate", Value::from(options.translate));
+  map.insert(
+    "initial_prompt",
+    serde_json::to_value(&options.initial_prompt)?,
+  );
+  // 逐字時間另存，sidecar不支援時不會產生
+  map.insert(
+    "words_path",
+    Value::String(handle(
+      build_path(code, WORDS_FILE),
+      "Inserting words_path",
+    )?),
   );
 
   let response = handle(
@@ -175,10 +302,21 @@ pub async fn gen_subtitle(code: &str) -> Result<(), Error> {
 pub async fn merge_video_and_subtitle(code: &str) -> Result<(), Error> {
   log::info!("Merging video and subtitle subtitle for code: {}", &code);
 
-  let subtitles = handle(
+  let mut subtitles = handle(
     database::get_subtitles(code),
     &format!("Getting subtitles for code: {}", code),
   )?;
+  // 字型ID轉為sidecar使用的路徑，找不到時使用預設字型
+  for subtitle in subtitles.iter_mut() {
+    subtitle.font = match fonts::resolve(&subtitle.font).or_else(|| fonts::font_path(DEFAULT_FONT))
+    {
+      Some(path) => path,
+      None => {
+        log::error!("Font '{}' not found for code: {}", subtitle.font, code);
+        return Err(Error::new(ErrorKind::NotFound, "Font not found"));
+      }
+    };
+  }
 
   let video_path = handle(get_file_path(code, RESULT_FILE), "Inserting video_path")?;
 
@@ -192,6 +330,11 @@ pub async fn merge_video_and_subtitle(code: &str) -> Result<(), Error> {
   data.insert("subtitles", serde_json::to_value(subtitles)?);
   data.insert("video_path", Value::String(video_path));
   data.insert("output_path", Value::String(output_path));
+  if let Some(ass_path) = karaoke_subtitles(code)? {
+    data.insert("ass_path", Value::String(ass_path));
+  }
+  data.insert("chapters", chapter_metadata(code)?);
+  data.insert("encoding", encoding_options(code)?);
 
   let response = handle(
     make_request("http://localhost:5000/set_subtitle", &data).await,
@@ -206,14 +349,394 @@ pub async fn merge_video_and_subtitle(code: &str) -> Result<(), Error> {
   }
 }
 
+pub async fn merge_video_and_subtitle_file(code: &str) -> Result<(), Error> {
+  log::info!("Merging video and subtitle file for code: {}", &code);
+
+  let mut data = HashMap::new();
+  data.insert(
+    "subtitle_path",
+    Value::String(handle(
+      get_file_path(code, SUBS_FILE),
+      "Inserting subtitles_path",
+    )?),
+  );
+  data.insert(
+    "video_path",
+    Value::String(handle(
+      get_file_path(code, RESULT_FILE),
+      "Inserting video_path",
+    )?),
+  );
+  data.insert(
+    "output_path",
+    Value::String(handle(
+      create_file(code, RESULT_WITH_SUBS_FILE),
+      "Inserting output_path",
+    )?),
+  );
+  if let Some(ass_path) = karaoke_subtitles(code)? {
+    data.insert("ass_path", Value::String(ass_path));
+  }
+  data.insert("chapters", chapter_metadata(code)?);
+  data.insert("encoding", encoding_options(code)?);
+
+  let response = handle(
+    make_request("http://localhost:5000/merge_video_and_subtitle", &data).await,
+    "Making request",
+  )?;
+
+  if response.status().is_success() {
+    log::info!("Python merge video and subtitle subtitle success");
+    Ok(())
+  } else {
+    Err(Error::new(ErrorKind::Other, ""))
+  }
+}
+
+// 合成字幕時的來源：編輯過的字幕、Whisper產生的srt或沒有字幕
+pub enum SubtitleSource {
+  Database,
+  File,
+  None,
+}
+
+pub fn subtitle_source(code: &str) -> Result<SubtitleSource, Error> {
+  let subtitles = handle(
+    database::get_subtitles(code),
+    &format!("Getting subtitles for code: {}", code),
+  )?;
+  if !subtitles.is_empty() {
+    Ok(SubtitleSource::Database)
+  } else if get_file_path(code, SUBS_FILE).is_ok() {
+    Ok(SubtitleSource::File)
+  } else {
+    Ok(SubtitleSource::None)
+  }
+}
+
+// 沒有字幕時只將章節寫入合成後的影片
+pub async fn write_chapters(code: &str) -> Result<(), Error> {
+  log::info!("Writing chapters into video for code: {}", code);
+
+  let mut data = HashMap::new();
+  data.insert(
+    "video_path",
+    Value::String(handle(
+      get_file_path(code, RESULT_FILE),
+      "Inserting video_path",
+    )?),
+  );
+  data.insert("chapters", chapter_metadata(code)?);
+
+  let response = handle(
+    make_request("http://localhost:5000/set_chapters", &data).await,
+    "Making request",
+  )?;
+
+  if response.status().is_success() {
+    log::info!("Python write chapters success");
+    Ok(())
+  } else {
+    Err(Error::new(ErrorKind::Other, ""))
+  }
+}
+
+pub async fn trim_preview(code: &str, seconds: u32) -> Result<(), Error> {
+  log::info!("Trimming preview to {} seconds for code: {}", seconds, code);
+
+  // 保留原始影片與音訊，預覽另外輸出，之後可轉為完整生成
+  let video_path = handle(get_file_path(code, VIDEO_FILE), "Getting video_path")?;
+  let audio_path = handle(get_file_path(code, AUDIO_FILE), "Getting audio_path")?;
+  let source_video_path = handle(
+    build_path(code, SOURCE_VIDEO_FILE),
+    "Getting source_video_path",
+  )?;
+  let source_audio_path = handle(
+    build_path(code, SOURCE_AUDIO_FILE),
+    "Getting source_audio_path",
+  )?;
+  handle(
+    std::fs::rename(&video_path, &source_video_path),
+    &format!("Moving '{}' to '{}'", video_path, source_video_path),
+  )?;
+  handle(
+    std::fs::rename(&audio_path, &source_audio_path),
+    &format!("Moving '{}' to '{}'", audio_path, source_audio_path),
+  )?;
+
+  let mut data = HashMap::new();
+  data.insert("video_path", Value::String(source_video_path));
+  data.insert("audio_path", Value::String(source_audio_path));
+  data.insert("output_video_path", Value::String(video_path));
+  data.insert("output_audio_path", Value::String(audio_path));
+  data.insert("duration", Value::from(seconds));
+
+  let response = handle(
+    make_request("http://localhost:5000/trim_preview", &data).await,
+    "Making request",
+  )?;
+
+  if response.status().is_success() {
+    log::info!("Python trim preview success");
+    Ok(())
+  } else {
+    Err(Error::new(ErrorKind::Other, ""))
+  }
+}
+
+// 預覽轉為完整生成時還原原始影片與音訊，並清除預覽產生的字幕
+pub fn promote_preview(code: &str) -> Result<(), Error> {
+  let source_video_path = match get_file_path(code, SOURCE_VIDEO_FILE) {
+    Ok(path) => path,
+    Err(_) => return Ok(()),
+  };
+  log::info!("Promoting preview to full render for code: {}", code);
+
+  let video_path = handle(build_path(code, VIDEO_FILE), "Getting video_path")?;
+  handle(
+    std::fs::rename(&source_video_path, &video_path),
+    &format!("Moving '{}' to '{}'", source_video_path, video_path),
+  )?;
+  if let Ok(source_audio_path) = get_file_path(code, SOURCE_AUDIO_FILE) {
+    let audio_path = handle(build_path(code, AUDIO_FILE), "Getting audio_path")?;
+    handle(
+      std::fs::rename(&source_audio_path, &audio_path),
+      &format!("Moving '{}' to '{}'", source_audio_path, audio_path),
+    )?;
+  }
+
+  handle(
+    database::reset_task_subtitles(code),
+    &format!("Resetting subtitles for code: {}", code),
+  )
+}
+
+// 響度標準化與降噪，直接覆寫音檔
+pub async fn process_audio(code: &str, options: &AudioOptions) -> Result<(), Error> {
+  log::info!("Processing audio for code: {}", code);
+
+  let audio_path = handle(get_file_path(code, AUDIO_FILE), "Inserting audio_path")?;
+  let mut data = HashMap::new();
+  data.insert("input_path", Value::String(audio_path.clone()));
+  data.insert("output_path", Value::String(audio_path));
+  data.insert("normalize", Value::from(options.normalize));
+  data.insert("target_lufs", Value::from(options.target_lufs));
+  data.insert(
+    "noise_reduction",
+    serde_json::to_value(options.noise_reduction)?,
+  );
+
+  let response = handle(
+    make_request("http://localhost:5000/process_audio", &data).await,
+    "Making request",
+  )?;
+
+  if response.status().is_success() {
+    log::info!("Python process audio success");
+    Ok(())
+  } else {
+    Err(Error::new(ErrorKind::Other, ""))
+  }
+}
+
+// 由sidecar將PDF每頁轉為圖片，回傳圖片路徑
+async fn render_pdf(code: &str) -> Result<Vec<String>, Error> {
+  log::info!("Rendering PDF pages for code: {}", code);
+
+  let mut data = HashMap::new();
+  data.insert(
+    "pdf_path",
+    handle(get_file_path(code, SLIDES_PDF_FILE), "Inserting pdf_path")?,
+  );
+  data.insert(
+    "output_dir",
+    handle(get_file_path(code, SLIDES_DIR), "Inserting output_dir")?,
+  );
+
+  let response = handle(
+    make_request("http://localhost:5000/render_pdf", &data).await,
+    "Making request",
+  )?;
+  if !response.status().is_success() {
+    return Err(Error::new(ErrorKind::Other, ""));
+  }
+
+  let response = handle(
+    response.json::<RenderPdfResponse>().await,
+    "Parsing render_pdf response",
+  )?;
+  log::info!("Python render PDF success: {} pages", response.slides.len());
+  Ok(response.slides)
+}
+
+pub async fn build_slideshow(code: &str, slideshow: &Slideshow) -> Result<(), Error> {
+  log::info!("Building slideshow for code: {}", code);
+
+  let slides = match slideshow.pdf {
+    true => render_pdf(code).await?,
+    false => slideshow
+      .slides
+      .iter()
+      .map(|slide| get_file_path(code, slide))
+      .collect::<Result<Vec<String>, Error>>()?,
+  };
+  for timing in [&slideshow.durations, &slideshow.timestamps] {
+    if !timing.is_empty() && timing.len() != slides.len() {
+      log::error!(
+        "{} slide timings for {} slides for code: {}",
+        timing.len(),
+        slides.len(),
+        code
+      );
+      return Err(Error::new(ErrorKind::InvalidData, "Slide count mismatch"));
+    }
+  }
+
+  // 未指定秒數時依旁白長度決定，timestamps的最後一張持續到旁白結束
+  let mut data = HashMap::new();
+  data.insert("slides", serde_json::to_value(slides)?);
+  data.insert("durations", serde_json::to_value(&slideshow.durations)?);
+  data.insert("timestamps", serde_json::to_value(&slideshow.timestamps)?);
+  data.insert(
+    "audio_path",
+    Value::String(handle(
+      get_file_path(code, AUDIO_FILE),
+      "Inserting audio_path",
+    )?),
+  );
+  data.insert(
+    "output_path",
+    Value::String(handle(
+      create_file(code, VIDEO_FILE),
+      "Inserting output_path",
+    )?),
+  );
+  data.insert("encoding", encoding_options(code)?);
+
+  let response = handle(
+    make_request("http://localhost:5000/build_slideshow", &data).await,
+    "Making request",
+  )?;
+
+  if response.status().is_success() {
+    log::info!("Python build slideshow success");
+    Ok(())
+  } else {
+    Err(Error::new(ErrorKind::Other, ""))
+  }
+}
+
+// 由sidecar偵測投影片切換，依切換點產生章節
+pub async fn detect_chapters(code: &str, options: &ChapterOptions) -> Result<(), Error> {
+  log::info!("Detecting chapters for code: {}", code);
+
+  let video_path = handle(get_file_path(code, VIDEO_FILE), "Inserting video_path")?;
+  let duration = handle(
+    get_video_duration(&video_path),
+    &format!("Getting video duration for code: {}", code),
+  )?;
+
+  let mut data = HashMap::new();
+  data.insert("video_path", Value::String(video_path));
+  data.insert("threshold", Value::from(options.threshold));
+  data.insert("min_duration", Value::from(options.min_duration));
+
+  let response = handle(
+    make_request("http://localhost:5000/detect_scenes", &data).await,
+    "Making request",
+  )?;
+  if !response.status().is_success() {
+    return Err(Error::new(ErrorKind::Other, ""));
+  }
+
+  let response = handle(
+    response.json::<DetectScenesResponse>().await,
+    "Parsing detect_scenes response",
+  )?;
+  let scenes: Vec<u64> = response
+    .timestamps
+    .iter()
+    .filter(|time| time.is_finite() && **time > 0.0)
+    .map(|time| (time * 1000.0).round() as u64)
+    .collect();
+  let subtitles = load_subtitles(code)?.unwrap_or_default();
+
+  let chapters = chapters::build_chapters(
+    &scenes,
+    duration,
+    options.min_duration as u64 * 1000,
+    &subtitles,
+  );
+  chapters::save_chapters(code, &chapters)?;
+  log::info!("Python detect scenes success: {} chapters", chapters.len());
+  Ok(())
+}
+
+// 最終合成時寫入MP4章節，時間以毫秒表示
+fn chapter_metadata(code: &str) -> Result<Value, Error> {
+  let chapters = handle(
+    database::get_chapters(code),
+    &format!("Getting chapters for code: {}", code),
+  )?;
+  let metadata: Vec<Value> = chapters
+    .iter()
+    .filter_map(|chapter| {
+      Some(json!({
+        "start": parse_time(&chapter.start_time)?,
+        "end": parse_time(&chapter.end_time)?,
+        "title": chapter.title,
+      }))
+    })
+    .collect();
+  Ok(Value::Array(metadata))
+}
+
+// karaoke模式下輸出ASS字幕供燒錄，沒有逐字時間時仍以整句顯示
+fn karaoke_subtitles(code: &str) -> Result<Option<String>, Error> {
+  let task = handle(
+    database::get_task_info(code),
+    &format!("Getting task info for code: {}", code),
+  )?;
+  if !task.karaoke {
+    return Ok(None);
+  }
+
+  let subtitles = match load_subtitles(code)? {
+    Some(subtitles) => subtitles,
+    None => return Ok(None),
+  };
+  let ass_path = handle(create_file(code, SUBS_ASS_FILE), "Creating ASS file")?;
+  handle(
+    std::fs::write(&ass_path, to_ass(&subtitles, true)),
+    &format!("Writing file '{}'", ass_path),
+  )?;
+  Ok(Some(ass_path))
+}
+
+// 未設定時使用standard的編碼參數
+fn encoding_options(code: &str) -> Result<Value, Error> {
+  let options = handle(
+    database::get_encoding_options(code),
+    &format!("Getting encoding options for code: {}", code),
+  )?
+  .unwrap_or_else(|| Preset::Standard.encoding());
+  Ok(serde_json::to_value(options)?)
+}
+
 pub fn send_email(email: &str, code: &str, success: bool) -> Result<(), Error> {
   log::info!("Sending email");
 
   let body = match success {
-    true => format!(
-      "Hi, your video is ready, please download it from: http://localhost:3000/{}",
-      code
-    ),
+    true => {
+      let link = handle(
+        signer::sign_download(code, DOWNLOAD_RESOURCE, EMAIL_LINK_TTL, false),
+        &format!("Signing download link for code: {}", code),
+      )?;
+      format!(
+        "Hi, your video is ready, please download it from: {}",
+        link.to_url()
+      )
+    }
     false => String::from("Video generation failed."),
   };
 
@@ -245,6 +768,56 @@ pub fn send_email(email: &str, code: &str, success: bool) -> Result<(), Error> {
   Ok(())
 }
 
+pub async fn replace_background(code: &str, background: &Background) -> Result<(), Error> {
+  log::info!("Replacing background for code: {}", &code);
+
+  let mut data = HashMap::new();
+  data.insert(
+    "image_path",
+    Value::String(handle(
+      get_file_path(code, DEBG_AVATAR_FILE),
+      "Inserting image_path",
+    )?),
+  );
+  data.insert(
+    "original_path",
+    Value::String(handle(
+      get_file_path(code, AVATAR_FILE),
+      "Inserting original_path",
+    )?),
+  );
+  if let (BackgroundMode::Image, Some(image)) = (background.mode, &background.image) {
+    data.insert(
+      "background_path",
+      Value::String(handle(
+        get_file_path(code, image),
+        "Inserting background_path",
+      )?),
+    );
+  }
+  data.insert("mode", serde_json::to_value(background.mode)?);
+  data.insert("color", Value::String(background.color.clone()));
+  data.insert(
+    "output_path",
+    Value::String(handle(
+      create_file(code, BG_AVATAR_FILE),
+      "Inserting output_path",
+    )?),
+  );
+
+  let response = handle(
+    make_request("http://localhost:5000/replace_background", &data).await,
+    "Making request",
+  )?;
+
+  if response.status().is_success() {
+    log::info!("Python replace background success");
+    Ok(())
+  } else {
+    Err(Error::new(ErrorKind::Other, ""))
+  }
+}
+
 pub async fn remove_background(code: &str) -> Result<(), Error> {
   log::info!("Removing background for code: {}", &code);
 
@@ -1,14 +1,26 @@
 use crate::{
   model::{
-    subtitle::{self, Subtitle},
+    audio::AudioOptions,
+    avatar::{Background, Keyframe},
+    chapter::Chapter,
+    constant::DOWNLOAD_CONTINUATION_SECONDS,
+    generation::{EncodingOptions, GenerationOptions},
+    revision::{Revision, ORIGINAL_REVISION},
+    search::Hit,
+    subtitle::{ReadingSpeedWarning, Subtitle},
     task::{
       Status::{self, Finish, Processing},
       Task,
     },
+    transcription::TranscriptionOptions,
+    translation::Track,
+    worker::GenVideoRequest,
   },
+  search,
   utils::*,
 };
-use rusqlite::{params, Connection, Result};
+use chrono::NaiveDateTime;
+use rusqlite::{params, params_from_iter, Connection, Result, Transaction, TransactionBehavior};
 use std::env;
 
 fn connect_to_db() -> Result<Connection, Error> {
@@ -21,7 +33,7 @@ fn connect_to_db() -> Result<Connection, Error> {
 
 pub fn init_db() {
   log::info!("Initializing db");
-  let conn = Connection::open("./slidetalker.db3").unwrap_or_else(|e| {
+  let mut conn = Connection::open("./slidetalker.db3").unwrap_or_else(|e| {
     log::error!("Failed to open SQLite connection: {}", e);
     panic!("Failed to open SQLite connection");
   });
@@ -46,10 +58,141 @@ pub fn init_db() {
       panic!("Failed to create table");
     });
 
+  add_column(&conn, "task", "keep_files BOOLEAN NOT NULL DEFAULT 0");
+  add_column(&conn, "task", "timeline TEXT");
+  add_column(&conn, "task", "generation_options TEXT");
+  add_column(&conn, "task", "encoding_options TEXT");
+  add_column(&conn, "task", "audio_options TEXT");
+  add_column(&conn, "task", "transcription_options TEXT");
+  add_column(&conn, "task", "karaoke BOOLEAN NOT NULL DEFAULT 0");
+  add_column(&conn, "task", "chapters TEXT");
+  add_column(&conn, "task", "background_options TEXT");
+  add_column(&conn, "task", "render_request TEXT");
+  add_column(&conn, "task", "reading_speed_warnings TEXT");
+
+  conn
+    .execute(
+      "CREATE TABLE IF NOT EXISTS download_token (
+      nonce VARCHAR(32) NOT NULL,
+      code VARCHAR(10) NOT NULL,
+      used BOOLEAN NOT NULL DEFAULT 0,
+      client_ip VARCHAR(45),
+      PRIMARY KEY (nonce)
+    );",
+      (),
+    )
+    .unwrap_or_else(|e| {
+      log::error!("Failed to create table: {}", e);
+      panic!("Failed to create table");
+    });
+
+  add_column(&conn, "download_token", "client_ip VARCHAR(45)");
+  add_column(&conn, "download_token", "used_at INTEGER");
+
+  conn
+    .execute(
+      "CREATE TABLE IF NOT EXISTS subtitle_revision (
+      code VARCHAR(10) NOT NULL,
+      revision INTEGER NOT NULL,
+      subtitles TEXT NOT NULL,
+      author VARCHAR(64) NOT NULL,
+      created_at DATETIME NOT NULL,
+      PRIMARY KEY (code, revision)
+    );",
+      (),
+    )
+    .unwrap_or_else(|e| {
+      log::error!("Failed to create table: {}", e);
+      panic!("Failed to create table");
+    });
+
+  conn
+    .execute(
+      "CREATE TABLE IF NOT EXISTS subtitle_track (
+      code VARCHAR(10) NOT NULL,
+      language VARCHAR(35) NOT NULL,
+      subtitles TEXT NOT NULL,
+      created_at DATETIME NOT NULL,
+      PRIMARY KEY (code, language)
+    );",
+      (),
+    )
+    .unwrap_or_else(|e| {
+      log::error!("Failed to create table: {}", e);
+      panic!("Failed to create table");
+    });
+
+  // 字幕全文索引，由update_task_subtitles維護
+  conn
+    .execute(
+      "CREATE VIRTUAL TABLE IF NOT EXISTS subtitle_fts USING fts5(
+      code UNINDEXED,
+      start_time UNINDEXED,
+      end_time UNINDEXED,
+      text
+    );",
+      (),
+    )
+    .unwrap_or_else(|e| {
+      log::error!("Failed to create table: {}", e);
+      panic!("Failed to create table");
+    });
+
+  if let Err(e) = backfill_subtitle_index(&mut conn) {
+    log::error!("Failed to backfill subtitle index: {}", e);
+  }
+
   log::info!("Initialization completed successfully");
 }
 
-pub fn insert_task(code: &str, subs: bool) -> Result<(), Error> {
+// 建立全文索引前已有字幕的任務，補上索引
+fn backfill_subtitle_index(conn: &mut Connection) -> Result<(), Error> {
+  let tx = handle(conn.transaction(), "Starting transaction")?;
+  let tasks = {
+    let mut stmt = handle(
+      tx.prepare(
+        "SELECT code, subtitles FROM task WHERE subtitles IS NOT NULL
+        AND code NOT IN (SELECT DISTINCT code FROM subtitle_fts)",
+      ),
+      "Preparing select operation",
+    )?;
+    let rows = handle(
+      stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+      }),
+      "Querying operation",
+    )?;
+
+    let mut tasks = Vec::new();
+    for task in rows {
+      tasks.push(handle(task, "Getting row data operation")?);
+    }
+    tasks
+  };
+
+  for (code, json_str) in tasks {
+    let subs: Vec<Subtitle> = match serde_json::from_str(&json_str) {
+      Ok(subs) => subs,
+      Err(e) => {
+        log::warn!("Skipping subtitle index for code {}: {}", code, e);
+        continue;
+      }
+    };
+    log::info!("Backfilling subtitle index for code: {}", code);
+    index_subtitles(&tx, &code, &subs)?;
+  }
+  handle(tx.commit(), "Committing transaction")?;
+  Ok(())
+}
+
+// 舊資料庫補上新欄位，欄位已存在時忽略
+fn add_column(conn: &Connection, table: &str, column: &str) {
+  if let Err(e) = conn.execute(&format!("ALTER TABLE {} ADD COLUMN {}", table, column), ()) {
+    log::debug!("Skipping column '{}': {}", column, e);
+  }
+}
+
+pub fn insert_task(code: &str, subs: bool, keep_files: bool) -> Result<(), Error> {
   log::info!("Inserting task with code: {}", code);
   let conn = connect_to_db()?;
 
@@ -60,8 +203,8 @@ pub fn insert_task(code: &str, subs: bool) -> Result<(), Error> {
 
   handle(
     conn.execute(
-      "INSERT INTO task (code, status, date, subs_status, video_status) VALUES (?1, ?2, ?3, ?4, ?5)",
-      params![code, Processing.to_string(), get_date(), subs_status, Processing.to_string()],
+      "INSERT INTO task (code, status, date, subs_status, video_status, keep_files) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+      params![code, Processing.to_string(), get_date(), subs_status, Processing.to_string(), keep_files],
     ),
     "Executeing insert operation",
   )?;
@@ -86,11 +229,15 @@ pub fn get_task_info(code: &str) -> Result<Task, Error> {
     let status: Status = handle(row.get(2), "Getting row data operation")?;
     let subs_status: Status = handle(row.get(4), "Getting row data operation")?;
     let video_status: Status = handle(row.get(6), "Getting row data operation")?;
+    let keep_files: bool = handle(row.get("keep_files"), "Getting row data operation")?;
+    let karaoke: bool = handle(row.get("karaoke"), "Getting row data operation")?;
     Ok(Task {
       code: code,
       status: status,
       subs_status: subs_status,
       video_status: video_status,
+      keep_files,
+      karaoke,
     })
   } else {
     Err(Error::new(ErrorKind::Other, "No task found"))
@@ -109,12 +256,17 @@ pub fn get_subtitles(code: &str) -> Result<Vec<Subtitle>, Error> {
   let row = handle(rows.next(), "Finding next row")?;
 
   if let Some(row) = row {
-    let json_str: String = handle(row.get(0), "Getting row data operation")?;
+    let json_str: Option<String> = handle(row.get(0), "Getting row data operation")?;
 
-    // 将 JSON 数据解析为 MyData 结构体
-    let data: subtitle::Request =
-      serde_json::from_str(&json_str).expect("JSON deserialization failed");
-    Ok(data.subtitles)
+    // 尚未儲存過字幕時為NULL
+    let data: Vec<Subtitle> = match json_str {
+      Some(json_str) => handle(
+        serde_json::from_str(&json_str),
+        "JSON deserialization operation",
+      )?,
+      None => Vec::new(),
+    };
+    Ok(data)
   } else {
     Err(Error::new(ErrorKind::Other, "No task found"))
   }
@@ -206,14 +358,313 @@ pub fn update_task_email(code: &str, email: &str) -> Result<(), Error> {
   Ok(())
 }
 
-pub fn update_task_subtitles(code: &str, subs: &Vec<Subtitle>) -> Result<(), Error> {
-  log::info!("Updating task email with code: {}", code);
-  let conn = connect_to_db()?;
+// 每次儲存都新增一個版本，回傳版本號
+// expected與目前最新版本不同時不寫入並回傳None
+pub fn update_task_subtitles(
+  code: &str,
+  subs: &Vec<Subtitle>,
+  author: &str,
+  expected: Option<u32>,
+) -> Result<Option<u32>, Error> {
+  log::info!("Updating task subtitles with code: {}", code);
+  let mut conn = connect_to_db()?;
+
+  let json_str = serde_json::to_string(&subs).expect("JSON serialization failed");
+  // 先取得寫入鎖再讀取最新版本，避免同時寫入時版本檢查失效
+  let tx = handle(
+    conn.transaction_with_behavior(TransactionBehavior::Immediate),
+    "Starting transaction",
+  )?;
+  let latest: Option<u32> = handle(
+    tx.query_row(
+      "SELECT MAX(revision) FROM subtitle_revision WHERE code = ?1",
+      params![code],
+      |row| row.get(0),
+    ),
+    "Querying operation",
+  )?;
+  if expected.is_some() && expected != latest {
+    log::warn!(
+      "Subtitle revision conflict for code: {}, expected {:?}, latest {:?}",
+      code,
+      expected,
+      latest
+    );
+    return Ok(None);
+  }
+
+  // 閱讀速度警告只針對自動斷行的結果，編輯後不再適用
+  handle(
+    tx.execute(
+      "UPDATE task SET subtitles = ?1, reading_speed_warnings = NULL WHERE code = ?2",
+      params![json_str, code],
+    ),
+    "Executing update Operation",
+  )?;
+  let revision = latest.map_or(1, |latest| latest + 1);
+  handle(
+    tx.execute(
+      "INSERT INTO subtitle_revision (code, revision, subtitles, author, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+      params![code, revision, json_str, author, get_datetime()],
+    ),
+    "Executeing insert operation",
+  )?;
+  index_subtitles(&tx, code, subs)?;
+  handle(tx.commit(), "Committing transaction")?;
+
+  log::info!("Update completed successfully, revision: {}", revision);
+  Ok(Some(revision))
+}
+
+// 保存Whisper產生的原始字幕為第0版，已存在時忽略
+pub fn insert_original_revision(
+  code: &str,
+  subs: &Vec<Subtitle>,
+  author: &str,
+) -> Result<(), Error> {
+  log::info!("Inserting original subtitle revision with code: {}", code);
+  let mut conn = connect_to_db()?;
 
   let json_str = serde_json::to_string(&subs).expect("JSON serialization failed");
+  let tx = handle(conn.transaction(), "Starting transaction")?;
+  let inserted = handle(
+    tx.execute(
+      "INSERT OR IGNORE INTO subtitle_revision (code, revision, subtitles, author, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+      params![code, ORIGINAL_REVISION, json_str, author, get_datetime()],
+    ),
+    "Executeing insert operation",
+  )?;
+  // 尚未被編輯過的字幕也要能被搜尋
+  if inserted == 1 {
+    index_subtitles(&tx, code, subs)?;
+  }
+  handle(tx.commit(), "Committing transaction")?;
+
+  log::info!("Insertion completed successfully");
+  Ok(())
+}
+
+// 重建該任務的全文索引，CJK字間加上空白讓unicode61可逐字比對
+fn index_subtitles(tx: &Transaction, code: &str, subs: &Vec<Subtitle>) -> Result<(), Error> {
+  handle(
+    tx.execute("DELETE FROM subtitle_fts WHERE code = ?1", params![code]),
+    "Executing delete operation",
+  )?;
+  for sub in subs {
+    handle(
+      tx.execute(
+        "INSERT INTO subtitle_fts (code, start_time, end_time, text) VALUES (?1, ?2, ?3, ?4)",
+        params![
+          code,
+          sub.start_time,
+          sub.end_time,
+          search::fts_text(&sub.text)
+        ],
+      ),
+      "Executeing insert operation",
+    )?;
+  }
+  Ok(())
+}
+
+// 只在codes內搜尋，依相關度排序，最多回傳limit筆
+pub fn search_subtitles(
+  codes: &[String],
+  query: &str,
+  limit: u32,
+  offset: u32,
+) -> Result<Vec<(String, Hit)>, Error> {
+  log::info!("Searching subtitles in {} tasks", codes.len());
+  let conn = connect_to_db()?;
+
+  let placeholders = vec!["?"; codes.len()].join(", ");
+  let sql = format!(
+    "SELECT code, start_time, end_time, snippet(subtitle_fts, 3, '{}', '{}', '…', 16)
+    FROM subtitle_fts WHERE subtitle_fts MATCH ? AND code IN ({}) ORDER BY rank LIMIT {} OFFSET {}",
+    search::MARK_START,
+    search::MARK_END,
+    placeholders,
+    limit,
+    offset
+  );
+  let mut stmt = handle(conn.prepare(&sql), "Preparing select operation")?;
+  let params = std::iter::once(query).chain(codes.iter().map(String::as_str));
+  let rows = handle(
+    stmt.query_map(params_from_iter(params), |row| {
+      Ok((
+        row.get::<_, String>(0)?,
+        Hit {
+          start_time: row.get(1)?,
+          end_time: row.get(2)?,
+          snippet: search::clean_snippet(&row.get::<_, String>(3)?),
+        },
+      ))
+    }),
+    "Querying operation",
+  )?;
+
+  let mut hits = Vec::new();
+  for hit in rows {
+    hits.push(handle(hit, "Getting row data operation")?);
+  }
+  log::info!("Search completed successfully, {} hits", hits.len());
+  Ok(hits)
+}
+
+pub fn get_latest_revision(code: &str) -> Result<Option<u32>, Error> {
+  log::info!("Getting latest subtitle revision with code: {}", code);
+  let conn = connect_to_db()?;
+
+  handle(
+    conn.query_row(
+      "SELECT MAX(revision) FROM subtitle_revision WHERE code = ?1",
+      params![code],
+      |row| row.get(0),
+    ),
+    "Querying operation",
+  )
+}
+
+pub fn get_revisions(code: &str) -> Result<Vec<Revision>, Error> {
+  log::info!("Getting subtitle revisions with code: {}", code);
+  let conn = connect_to_db()?;
+
+  let mut stmt = handle(
+    conn.prepare(
+      "SELECT revision, author, created_at FROM subtitle_revision WHERE code = ?1 ORDER BY revision",
+    ),
+    "Preparing select operation",
+  )?;
+  let rows = handle(
+    stmt.query_map(params![code], |row| {
+      let created_at: NaiveDateTime = row.get(2)?;
+      Ok(Revision {
+        revision: row.get(0)?,
+        author: row.get(1)?,
+        created_at: created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+      })
+    }),
+    "Querying operation",
+  )?;
+
+  let mut revisions = Vec::new();
+  for revision in rows {
+    revisions.push(handle(revision, "Getting row data operation")?);
+  }
+  Ok(revisions)
+}
+
+pub fn get_revision_subtitles(code: &str, revision: u32) -> Result<Option<Vec<Subtitle>>, Error> {
+  log::info!("Getting subtitle revision {} with code: {}", revision, code);
+  let conn = connect_to_db()?;
+
+  let mut stmt = handle(
+    conn.prepare("SELECT subtitles FROM subtitle_revision WHERE code = ?1 AND revision = ?2"),
+    "Preparing select operation",
+  )?;
+  let mut rows = handle(stmt.query(params![code, revision]), "Querying operation")?;
+  let row = handle(rows.next(), "Finding next row")?;
+
+  match row {
+    Some(row) => {
+      let json_str: String = handle(row.get(0), "Getting row data operation")?;
+      Ok(Some(handle(
+        serde_json::from_str(&json_str),
+        "JSON deserialization operation",
+      )?))
+    }
+    None => Ok(None),
+  }
+}
+
+pub fn get_timeline(code: &str) -> Result<Vec<Keyframe>, Error> {
+  log::info!("Getting timeline with code: {}", code);
+  let conn = connect_to_db()?;
+
+  let mut stmt = handle(
+    conn.prepare("SELECT timeline FROM task WHERE code = ?1"),
+    "Preparing select operation",
+  )?;
+  let mut rows = handle(stmt.query(params![code]), "Querying operation")?;
+  let row = handle(rows.next(), "Finding next row")?;
+
+  if let Some(row) = row {
+    let json_str: Option<String> = handle(row.get(0), "Getting row data operation")?;
+
+    let data: Vec<Keyframe> = match json_str {
+      Some(json_str) => handle(
+        serde_json::from_str(&json_str),
+        "JSON deserialization operation",
+      )?,
+      None => Vec::new(),
+    };
+    Ok(data)
+  } else {
+    Err(Error::new(ErrorKind::Other, "No task found"))
+  }
+}
+
+pub fn update_task_timeline(code: &str, timeline: &Vec<Keyframe>) -> Result<(), Error> {
+  log::info!("Updating task timeline with code: {}", code);
+  let conn = connect_to_db()?;
+
+  let json_str = handle(
+    serde_json::to_string(&timeline),
+    "JSON serialization operation",
+  )?;
+  handle(
+    conn.execute(
+      "UPDATE task SET timeline = ?1 WHERE code = ?2",
+      params![json_str, code],
+    ),
+    "Executing update Operation",
+  )?;
+
+  log::info!("Update completed successfully");
+  Ok(())
+}
+
+pub fn get_reading_speed_warnings(code: &str) -> Result<Vec<ReadingSpeedWarning>, Error> {
+  log::info!("Getting reading speed warnings with code: {}", code);
+  let conn = connect_to_db()?;
+
+  let mut stmt = handle(
+    conn.prepare("SELECT reading_speed_warnings FROM task WHERE code = ?1"),
+    "Preparing select operation",
+  )?;
+  let mut rows = handle(stmt.query(params![code]), "Querying operation")?;
+  let row = handle(rows.next(), "Finding next row")?;
+
+  if let Some(row) = row {
+    let json_str: Option<String> = handle(row.get(0), "Getting row data operation")?;
+
+    let data: Vec<ReadingSpeedWarning> = match json_str {
+      Some(json_str) => handle(
+        serde_json::from_str(&json_str),
+        "JSON deserialization operation",
+      )?,
+      None => Vec::new(),
+    };
+    Ok(data)
+  } else {
+    Err(Error::new(ErrorKind::Other, "No task found"))
+  }
+}
+
+pub fn update_task_reading_speed_warnings(
+  code: &str,
+  warnings: &Vec<ReadingSpeedWarning>,
+) -> Result<(), Error> {
+  log::info!("Updating task reading speed warnings with code: {}", code);
+  let conn = connect_to_db()?;
+
+  let json_str = handle(
+    serde_json::to_string(&warnings),
+    "JSON serialization operation",
+  )?;
   handle(
     conn.execute(
-      "UPDATE task SET subtitles = ?1 WHERE code = ?2",
+      "UPDATE task SET reading_speed_warnings = ?1 WHERE code = ?2",
       params![json_str, code],
     ),
     "Executing update Operation",
@@ -223,6 +674,463 @@ pub fn update_task_subtitles(code: &str, subs: &Vec<Subtitle>) -> Result<(), Err
   Ok(())
 }
 
+pub fn get_chapters(code: &str) -> Result<Vec<Chapter>, Error> {
+  log::info!("Getting chapters with code: {}", code);
+  let conn = connect_to_db()?;
+
+  let mut stmt = handle(
+    conn.prepare("SELECT chapters FROM task WHERE code = ?1"),
+    "Preparing select operation",
+  )?;
+  let mut rows = handle(stmt.query(params![code]), "Querying operation")?;
+  let row = handle(rows.next(), "Finding next row")?;
+
+  if let Some(row) = row {
+    let json_str: Option<String> = handle(row.get(0), "Getting row data operation")?;
+
+    let data: Vec<Chapter> = match json_str {
+      Some(json_str) => handle(
+        serde_json::from_str(&json_str),
+        "JSON deserialization operation",
+      )?,
+      None => Vec::new(),
+    };
+    Ok(data)
+  } else {
+    Err(Error::new(ErrorKind::Other, "No task found"))
+  }
+}
+
+pub fn update_task_chapters(code: &str, chapters: &Vec<Chapter>) -> Result<(), Error> {
+  log::info!("Updating task chapters with code: {}", code);
+  let conn = connect_to_db()?;
+
+  let json_str = handle(
+    serde_json::to_string(&chapters),
+    "JSON serialization operation",
+  )?;
+  handle(
+    conn.execute(
+      "UPDATE task SET chapters = ?1 WHERE code = ?2",
+      params![json_str, code],
+    ),
+    "Executing update Operation",
+  )?;
+
+  log::info!("Update completed successfully");
+  Ok(())
+}
+
+pub fn get_generation_options(code: &str) -> Result<Option<GenerationOptions>, Error> {
+  log::info!("Getting generation options with code: {}", code);
+  let conn = connect_to_db()?;
+
+  let mut stmt = handle(
+    conn.prepare("SELECT generation_options FROM task WHERE code = ?1"),
+    "Preparing select operation",
+  )?;
+  let mut rows = handle(stmt.query(params![code]), "Querying operation")?;
+  let row = handle(rows.next(), "Finding next row")?;
+
+  if let Some(row) = row {
+    let json_str: Option<String> = handle(row.get(0), "Getting row data operation")?;
+
+    match json_str {
+      Some(json_str) => Ok(Some(handle(
+        serde_json::from_str(&json_str),
+        "JSON deserialization operation",
+      )?)),
+      None => Ok(None),
+    }
+  } else {
+    Err(Error::new(ErrorKind::Other, "No task found"))
+  }
+}
+
+pub fn update_task_generation_options(
+  code: &str,
+  options: &GenerationOptions,
+) -> Result<(), Error> {
+  log::info!("Updating task generation options with code: {}", code);
+  let conn = connect_to_db()?;
+
+  let json_str = handle(
+    serde_json::to_string(&options),
+    "JSON serialization operation",
+  )?;
+  handle(
+    conn.execute(
+      "UPDATE task SET generation_options = ?1 WHERE code = ?2",
+      params![json_str, code],
+    ),
+    "Executing update Operation",
+  )?;
+
+  log::info!("Update completed successfully");
+  Ok(())
+}
+
+pub fn get_encoding_options(code: &str) -> Result<Option<EncodingOptions>, Error> {
+  log::info!("Getting encoding options with code: {}", code);
+  let conn = connect_to_db()?;
+
+  let mut stmt = handle(
+    conn.prepare("SELECT encoding_options FROM task WHERE code = ?1"),
+    "Preparing select operation",
+  )?;
+  let mut rows = handle(stmt.query(params![code]), "Querying operation")?;
+  let row = handle(rows.next(), "Finding next row")?;
+
+  if let Some(row) = row {
+    let json_str: Option<String> = handle(row.get(0), "Getting row data operation")?;
+
+    match json_str {
+      Some(json_str) => Ok(Some(handle(
+        serde_json::from_str(&json_str),
+        "JSON deserialization operation",
+      )?)),
+      None => Ok(None),
+    }
+  } else {
+    Err(Error::new(ErrorKind::Other, "No task found"))
+  }
+}
+
+pub fn update_task_encoding_options(code: &str, options: &EncodingOptions) -> Result<(), Error> {
+  log::info!("Updating task encoding options with code: {}", code);
+  let conn = connect_to_db()?;
+
+  let json_str = handle(
+    serde_json::to_string(&options),
+    "JSON serialization operation",
+  )?;
+  handle(
+    conn.execute(
+      "UPDATE task SET encoding_options = ?1 WHERE code = ?2",
+      params![json_str, code],
+    ),
+    "Executing update Operation",
+  )?;
+
+  log::info!("Update completed successfully");
+  Ok(())
+}
+
+pub fn update_task_karaoke(code: &str, karaoke: bool) -> Result<(), Error> {
+  log::info!("Updating task karaoke with code: {}", code);
+  let conn = connect_to_db()?;
+
+  handle(
+    conn.execute(
+      "UPDATE task SET karaoke = ?1 WHERE code = ?2",
+      params![karaoke, code],
+    ),
+    "Executing update Operation",
+  )?;
+
+  log::info!("Update completed successfully");
+  Ok(())
+}
+
+pub fn get_audio_options(code: &str) -> Result<Option<AudioOptions>, Error> {
+  log::info!("Getting audio options with code: {}", code);
+  let conn = connect_to_db()?;
+
+  let mut stmt = handle(
+    conn.prepare("SELECT audio_options FROM task WHERE code = ?1"),
+    "Preparing select operation",
+  )?;
+  let mut rows = handle(stmt.query(params![code]), "Querying operation")?;
+  let row = handle(rows.next(), "Finding next row")?;
+
+  if let Some(row) = row {
+    let json_str: Option<String> = handle(row.get(0), "Getting row data operation")?;
+
+    match json_str {
+      Some(json_str) => Ok(Some(handle(
+        serde_json::from_str(&json_str),
+        "JSON deserialization operation",
+      )?)),
+      None => Ok(None),
+    }
+  } else {
+    Err(Error::new(ErrorKind::Other, "No task found"))
+  }
+}
+
+pub fn update_task_audio_options(code: &str, options: &AudioOptions) -> Result<(), Error> {
+  log::info!("Updating task audio options with code: {}", code);
+  let conn = connect_to_db()?;
+
+  let json_str = handle(
+    serde_json::to_string(&options),
+    "JSON serialization operation",
+  )?;
+  handle(
+    conn.execute(
+      "UPDATE task SET audio_options = ?1 WHERE code = ?2",
+      params![json_str, code],
+    ),
+    "Executing update Operation",
+  )?;
+
+  log::info!("Update completed successfully");
+  Ok(())
+}
+
+pub fn get_background_options(code: &str) -> Result<Option<Background>, Error> {
+  log::info!("Getting background options with code: {}", code);
+  let conn = connect_to_db()?;
+
+  let mut stmt = handle(
+    conn.prepare("SELECT background_options FROM task WHERE code = ?1"),
+    "Preparing select operation",
+  )?;
+  let mut rows = handle(stmt.query(params![code]), "Querying operation")?;
+  let row = handle(rows.next(), "Finding next row")?;
+
+  if let Some(row) = row {
+    let json_str: Option<String> = handle(row.get(0), "Getting row data operation")?;
+
+    match json_str {
+      Some(json_str) => Ok(Some(handle(
+        serde_json::from_str(&json_str),
+        "JSON deserialization operation",
+      )?)),
+      None => Ok(None),
+    }
+  } else {
+    Err(Error::new(ErrorKind::Other, "No task found"))
+  }
+}
+
+pub fn update_task_background_options(code: &str, options: &Background) -> Result<(), Error> {
+  log::info!("Updating task background options with code: {}", code);
+  let conn = connect_to_db()?;
+
+  let json_str = handle(
+    serde_json::to_string(&options),
+    "JSON serialization operation",
+  )?;
+  handle(
+    conn.execute(
+      "UPDATE task SET background_options = ?1 WHERE code = ?2",
+      params![json_str, code],
+    ),
+    "Executing update Operation",
+  )?;
+
+  log::info!("Update completed successfully");
+  Ok(())
+}
+
+pub fn get_render_request(code: &str) -> Result<Option<GenVideoRequest>, Error> {
+  log::info!("Getting render request with code: {}", code);
+  let conn = connect_to_db()?;
+
+  let mut stmt = handle(
+    conn.prepare("SELECT render_request FROM task WHERE code = ?1"),
+    "Preparing select operation",
+  )?;
+  let mut rows = handle(stmt.query(params![code]), "Querying operation")?;
+  let row = handle(rows.next(), "Finding next row")?;
+
+  if let Some(row) = row {
+    let json_str: Option<String> = handle(row.get(0), "Getting row data operation")?;
+
+    match json_str {
+      Some(json_str) => Ok(Some(handle(
+        serde_json::from_str(&json_str),
+        "JSON deserialization operation",
+      )?)),
+      None => Ok(None),
+    }
+  } else {
+    Err(Error::new(ErrorKind::Other, "No task found"))
+  }
+}
+
+// 只有預覽任務會保存請求，None表示已轉為完整生成
+pub fn update_task_render_request(
+  code: &str,
+  request: Option<&GenVideoRequest>,
+) -> Result<(), Error> {
+  log::info!("Updating task render request with code: {}", code);
+  let conn = connect_to_db()?;
+
+  let json_str = match request {
+    Some(request) => Some(handle(
+      serde_json::to_string(request),
+      "JSON serialization operation",
+    )?),
+    None => None,
+  };
+  handle(
+    conn.execute(
+      "UPDATE task SET render_request = ?1 WHERE code = ?2",
+      params![json_str, code],
+    ),
+    "Executing update Operation",
+  )?;
+
+  log::info!("Update completed successfully");
+  Ok(())
+}
+
+pub fn update_task_transcription_options(
+  code: &str,
+  options: &TranscriptionOptions,
+) -> Result<(), Error> {
+  log::info!("Updating task transcription options with code: {}", code);
+  let conn = connect_to_db()?;
+
+  let json_str = handle(
+    serde_json::to_string(&options),
+    "JSON serialization operation",
+  )?;
+  handle(
+    conn.execute(
+      "UPDATE task SET transcription_options = ?1 WHERE code = ?2",
+      params![json_str, code],
+    ),
+    "Executing update Operation",
+  )?;
+
+  log::info!("Update completed successfully");
+  Ok(())
+}
+
+// 同語言的翻譯重新產生時覆寫
+pub fn upsert_subtitle_track(
+  code: &str,
+  language: &str,
+  subs: &Vec<Subtitle>,
+) -> Result<(), Error> {
+  log::info!("Upserting {} subtitle track with code: {}", language, code);
+  let conn = connect_to_db()?;
+
+  let json_str = serde_json::to_string(&subs).expect("JSON serialization failed");
+  handle(
+    conn.execute(
+      "INSERT OR REPLACE INTO subtitle_track (code, language, subtitles, created_at) VALUES (?1, ?2, ?3, ?4)",
+      params![code, language, json_str, get_datetime()],
+    ),
+    "Executeing insert operation",
+  )?;
+
+  log::info!("Insertion completed successfully");
+  Ok(())
+}
+
+pub fn get_subtitle_tracks(code: &str) -> Result<Vec<Track>, Error> {
+  log::info!("Getting subtitle tracks with code: {}", code);
+  let conn = connect_to_db()?;
+
+  let mut stmt = handle(
+    conn
+      .prepare("SELECT language, created_at FROM subtitle_track WHERE code = ?1 ORDER BY language"),
+    "Preparing select operation",
+  )?;
+  let rows = handle(
+    stmt.query_map(params![code], |row| {
+      let created_at: NaiveDateTime = row.get(1)?;
+      Ok(Track {
+        language: row.get(0)?,
+        created_at: created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+      })
+    }),
+    "Querying operation",
+  )?;
+
+  let mut tracks = Vec::new();
+  for track in rows {
+    tracks.push(handle(track, "Getting row data operation")?);
+  }
+  Ok(tracks)
+}
+
+pub fn get_subtitle_track(code: &str, language: &str) -> Result<Option<Vec<Subtitle>>, Error> {
+  log::info!("Getting {} subtitle track with code: {}", language, code);
+  let conn = connect_to_db()?;
+
+  let mut stmt = handle(
+    conn.prepare("SELECT subtitles FROM subtitle_track WHERE code = ?1 AND language = ?2"),
+    "Preparing select operation",
+  )?;
+  let mut rows = handle(stmt.query(params![code, language]), "Querying operation")?;
+  let row = handle(rows.next(), "Finding next row")?;
+
+  match row {
+    Some(row) => {
+      let json_str: String = handle(row.get(0), "Getting row data operation")?;
+      Ok(Some(handle(
+        serde_json::from_str(&json_str),
+        "JSON deserialization operation",
+      )?))
+    }
+    None => Ok(None),
+  }
+}
+
+pub fn insert_download_token(code: &str, nonce: &str) -> Result<(), Error> {
+  log::info!("Inserting download token with code: {}", code);
+  let conn = connect_to_db()?;
+
+  handle(
+    conn.execute(
+      "INSERT INTO download_token (nonce, code) VALUES (?1, ?2)",
+      params![nonce, code],
+    ),
+    "Executeing insert operation",
+  )?;
+
+  log::info!("Insertion completed successfully");
+  Ok(())
+}
+
+pub fn consume_download_token(
+  code: &str,
+  nonce: &str,
+  client_ip: Option<&str>,
+  continuation: bool,
+) -> Result<bool, Error> {
+  log::info!("Consuming download token with code: {}", code);
+  let conn = connect_to_db()?;
+
+  // 只有尚未使用過的token會被更新，同時記錄使用者IP與使用時間
+  let now = get_timestamp();
+  let updated = handle(
+    conn.execute(
+      "UPDATE download_token SET used = 1, client_ip = ?3, used_at = ?4 WHERE nonce = ?1 AND code = ?2 AND used = 0",
+      params![nonce, code, client_ip, now],
+    ),
+    "Executing update Operation",
+  )?;
+  log::debug!("updated={}", updated);
+  if updated == 1 {
+    return Ok(true);
+  }
+  if !continuation || client_ip.is_none() {
+    return Ok(false);
+  }
+
+  // 已使用的token只在短時間內接受同一個IP的續傳請求
+  let count: u32 = handle(
+    conn.query_row(
+      "SELECT COUNT(*) FROM download_token WHERE nonce = ?1 AND code = ?2 AND used = 1 AND client_ip = ?3 AND used_at >= ?4",
+      params![
+        nonce,
+        code,
+        client_ip,
+        now - DOWNLOAD_CONTINUATION_SECONDS
+      ],
+      |row| row.get(0),
+    ),
+    "Querying operation",
+  )?;
+  Ok(count == 1)
+}
+
 pub fn delete_task_by_code(code: &str) -> Result<(), Error> {
   log::info!("Deleting task in database by code");
   let conn = connect_to_db()?;
@@ -231,11 +1139,60 @@ pub fn delete_task_by_code(code: &str) -> Result<(), Error> {
     conn.execute("DELETE FROM task WHERE code = ?1", params![code]),
     "Executing delete operation",
   )?;
+  handle(
+    conn.execute("DELETE FROM download_token WHERE code = ?1", params![code]),
+    "Executing delete operation",
+  )?;
+  handle(
+    conn.execute(
+      "DELETE FROM subtitle_revision WHERE code = ?1",
+      params![code],
+    ),
+    "Executing delete operation",
+  )?;
+  handle(
+    conn.execute("DELETE FROM subtitle_track WHERE code = ?1", params![code]),
+    "Executing delete operation",
+  )?;
+  handle(
+    conn.execute("DELETE FROM subtitle_fts WHERE code = ?1", params![code]),
+    "Executing delete operation",
+  )?;
 
   log::info!("Deletion of task in database by code completed");
   Ok(())
 }
 
+// 清除字幕、版本與索引，重新生成字幕前使用
+pub fn reset_task_subtitles(code: &str) -> Result<(), Error> {
+  log::info!("Resetting task subtitles by code: {}", code);
+  let mut conn = connect_to_db()?;
+
+  let tx = handle(conn.transaction(), "Starting transaction")?;
+  handle(
+    tx.execute(
+      "UPDATE task SET subtitles = NULL, reading_speed_warnings = NULL WHERE code = ?1",
+      params![code],
+    ),
+    "Executing update Operation",
+  )?;
+  handle(
+    tx.execute(
+      "DELETE FROM subtitle_revision WHERE code = ?1",
+      params![code],
+    ),
+    "Executing delete operation",
+  )?;
+  handle(
+    tx.execute("DELETE FROM subtitle_fts WHERE code = ?1", params![code]),
+    "Executing delete operation",
+  )?;
+  handle(tx.commit(), "Committing transaction")?;
+
+  log::info!("Reset completed successfully");
+  Ok(())
+}
+
 pub fn search_task_by_date() -> Result<Vec<String>, Error> {
   // 搜尋存放超過一周的資料
   log::info!("Seaching task in database by date");
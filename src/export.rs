@@ -0,0 +1,175 @@
+use crate::{
+  database,
+  model::artifact::{Artifact, ARTIFACTS},
+  model::constant::*,
+  model::transcript,
+  subs::*,
+  utils::*,
+};
+use rand::Rng;
+use std::{
+  fs::{self, File},
+  io,
+  time::UNIX_EPOCH,
+};
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+pub fn list_artifacts(code: &str) -> Result<Vec<(Artifact, Option<u64>)>, Error> {
+  log::info!("Listing artifacts for code: {}", code);
+
+  let has_subtitles = load_subtitles(code)?.is_some();
+  let mut artifacts = Vec::new();
+
+  for artifact in ARTIFACTS {
+    if artifact.is_derived() {
+      if has_subtitles {
+        artifacts.push((artifact, None));
+      }
+    } else if let Ok(path) = get_file_path(code, artifact.file()) {
+      let size = fs::metadata(&path).map(|m| m.len()).ok();
+      artifacts.push((artifact, size));
+    }
+  }
+
+  log::debug!("artifacts={:?}", artifacts);
+  Ok(artifacts)
+}
+
+pub fn prepare_artifact(code: &str, artifact: Artifact) -> Result<Option<String>, Error> {
+  log::info!(
+    "Preparing artifact '{}' for code: {}",
+    artifact.name(),
+    code
+  );
+
+  if !artifact.is_derived() {
+    return Ok(get_file_path(code, artifact.file()).ok());
+  }
+
+  let subtitles = match load_subtitles(code)? {
+    Some(subtitles) => subtitles,
+    None => return Ok(None),
+  };
+  let content = match artifact {
+    Artifact::SubtitlesVtt => to_vtt(&subtitles),
+    Artifact::SubtitlesAss => to_ass(&subtitles, karaoke(code)?),
+    Artifact::Transcript => to_transcript_document(&subtitles, &transcript::Query::default()),
+    _ => to_srt(&subtitles),
+  };
+
+  // 內容未變時不重寫，保留修改時間供bundle判斷是否需要重建
+  if let Ok(path) = get_file_path(code, artifact.file()) {
+    if fs::read(&path).is_ok_and(|existing| existing == content.as_bytes()) {
+      return Ok(Some(path));
+    }
+  }
+  let path = write_atomic(code, artifact.file(), |file| {
+    io::Write::write_all(file, content.as_bytes())
+  })?;
+  Ok(Some(path))
+}
+
+pub fn build_bundle(code: &str) -> Result<Option<String>, Error> {
+  log::info!("Building bundle for code: {}", code);
+
+  let mut files = Vec::new();
+  for artifact in ARTIFACTS {
+    if let Some(path) = prepare_artifact(code, artifact)? {
+      files.push((artifact.file(), path));
+    }
+  }
+  if files.is_empty() {
+    log::warn!("No artifacts to bundle for code: {}", code);
+    return Ok(None);
+  }
+
+  // 所有檔案的大小與修改時間都沒變時沿用上次的bundle
+  let key = bundle_key(&files)?;
+  if let (Ok(path), Ok(key_path)) = (
+    get_file_path(code, BUNDLE_FILE),
+    get_file_path(code, BUNDLE_KEY_FILE),
+  ) {
+    if fs::read_to_string(&key_path).is_ok_and(|cached| cached == key) {
+      log::info!("Reusing bundle for code: {}", code);
+      return Ok(Some(path));
+    }
+  }
+
+  let path = write_atomic(code, BUNDLE_FILE, |file| {
+    let mut zip = ZipWriter::new(file);
+
+    for (name, path) in &files {
+      let mut source = File::open(path)?;
+      let size = source.metadata()?.len();
+      // 影片已經壓縮過，只壓縮文字檔
+      let method = match name.ends_with(".mp4") {
+        true => CompressionMethod::Stored,
+        false => CompressionMethod::Deflated,
+      };
+      let options = FileOptions::default()
+        .compression_method(method)
+        .large_file(size > u32::MAX as u64);
+
+      zip.start_file(*name, options)?;
+      io::copy(&mut source, &mut zip)?;
+    }
+
+    zip.finish()?;
+    Ok(())
+  })?;
+  write_atomic(code, BUNDLE_KEY_FILE, |file| {
+    io::Write::write_all(file, key.as_bytes())
+  })?;
+
+  log::info!("Bundle built for code: {}", code);
+  Ok(Some(path))
+}
+
+fn bundle_key(files: &[(&str, String)]) -> Result<String, Error> {
+  let mut key = String::new();
+  for (name, path) in files {
+    let metadata = handle(
+      fs::metadata(path),
+      &format!("Reading metadata of '{}'", path),
+    )?;
+    let modified = metadata
+      .modified()
+      .ok()
+      .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+      .map(|time| time.as_nanos())
+      .unwrap_or(0);
+    key.push_str(&format!("{} {} {}\n", name, metadata.len(), modified));
+  }
+  Ok(key)
+}
+
+// 先寫入暫存檔再改名，避免同時下載時讀到寫到一半的檔案
+fn write_atomic<F>(code: &str, filename: &str, write: F) -> Result<String, Error>
+where
+  F: FnOnce(&mut File) -> io::Result<()>,
+{
+  let tmp_name = format!("{:08x}_{}", rand::thread_rng().gen::<u32>(), filename);
+  let tmp_path = create_file(code, &tmp_name)?;
+  let path = build_path(code, filename)?;
+
+  let result = File::create(&tmp_path).and_then(|mut file| write(&mut file));
+  if let Err(e) = result {
+    let _ = fs::remove_file(&tmp_path);
+    return handle(Err(e), &format!("Writing file '{}'", tmp_path));
+  }
+
+  handle(
+    fs::rename(&tmp_path, &path),
+    &format!("Renaming file '{}'", tmp_path),
+  )?;
+  Ok(path)
+}
+
+// ASS只在任務開啟karaoke時加上逐字標示
+fn karaoke(code: &str) -> Result<bool, Error> {
+  let task = handle(
+    database::get_task_info(code),
+    &format!("Getting task info for code: {}", code),
+  )?;
+  Ok(task.karaoke)
+}
@@ -0,0 +1,225 @@
+use crate::{
+  model::{constant::*, font::Font},
+  utils::*,
+};
+use std::{
+  env,
+  fs::{self, File},
+  io::{Read, Seek, SeekFrom},
+  path::Path,
+};
+
+pub static FONT_EXTENSIONS: [&str; 3] = ["ttf", "otf", "ttc"];
+// name table通常只有數KB，超過時只讀取前段
+static MAX_NAME_TABLE_BYTES: usize = 1024 * 1024;
+
+pub fn font_dir() -> String {
+  let root = env::var("ROOT").expect("Failed to get root path");
+  format!("{}/fonts", root)
+}
+
+// 字型ID為檔名去掉副檔名，只允許英數字、底線與連字號
+pub fn is_valid_id(id: &str) -> bool {
+  !id.is_empty()
+    && id.len() <= 64
+    && id
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+pub fn font_path(id: &str) -> Option<String> {
+  font_path_in(&font_dir(), id)
+}
+
+// 尚未搬移到字型目錄時，預設字型沿用舊版放在工作目錄的檔案
+pub fn font_path_in(dir: &str, id: &str) -> Option<String> {
+  if !is_valid_id(id) {
+    return None;
+  }
+  FONT_EXTENSIONS
+    .iter()
+    .map(|ext| format!("{}/{}.{}", dir, id, ext))
+    .find(|path| Path::new(path).is_file())
+    .or_else(|| {
+      (id == DEFAULT_FONT && Path::new(LEGACY_DEFAULT_FONT_PATH).is_file())
+        .then(|| LEGACY_DEFAULT_FONT_PATH.to_string())
+    })
+}
+
+pub fn is_registered(id: &str) -> bool {
+  font_path(id).is_some()
+}
+
+pub fn resolve(font: &str) -> Option<String> {
+  resolve_in(&font_dir(), font)
+}
+
+// 舊資料的font為路徑，取檔名對應到字型ID
+pub fn resolve_in(dir: &str, font: &str) -> Option<String> {
+  font_path_in(dir, font).or_else(|| {
+    Path::new(font)
+      .file_stem()
+      .and_then(|stem| stem.to_str())
+      .and_then(|id| font_path_in(dir, id))
+  })
+}
+
+// 舊版的預設字型放在工作目錄，啟動時複製到字型目錄
+pub fn migrate_default_font() -> Result<(), Error> {
+  let dir = font_dir();
+  let target = format!("{}/{}.ttc", dir, DEFAULT_FONT);
+  if Path::new(&target).is_file() || !Path::new(LEGACY_DEFAULT_FONT_PATH).is_file() {
+    return Ok(());
+  }
+  log::info!(
+    "Migrating default font from '{}' to '{}'",
+    LEGACY_DEFAULT_FONT_PATH,
+    target
+  );
+
+  handle(
+    fs::create_dir_all(&dir),
+    &format!("Creating directory '{}'", dir),
+  )?;
+  handle(
+    fs::copy(LEGACY_DEFAULT_FONT_PATH, &target),
+    &format!("Copying file '{}'", LEGACY_DEFAULT_FONT_PATH),
+  )?;
+  Ok(())
+}
+
+// ASS樣式需要字型的family名稱
+pub fn font_family(font: &str) -> Option<String> {
+  let path = resolve(font)?;
+  let id = Path::new(&path).file_stem()?.to_str()?.to_string();
+  Some(
+    read_font_names(Path::new(&path))
+      .map(|(family, _)| family)
+      .unwrap_or_else(|| names_from_id(&id).0),
+  )
+}
+
+pub fn list_fonts() -> Result<Vec<Font>, Error> {
+  list_fonts_in(&font_dir())
+}
+
+pub fn list_fonts_in(dir: &str) -> Result<Vec<Font>, Error> {
+  log::info!("Listing registered fonts");
+
+  let entries = match fs::read_dir(dir) {
+    Ok(entries) => entries,
+    Err(_) => {
+      log::warn!("Font directory '{}' not found", dir);
+      return Ok(Vec::new());
+    }
+  };
+
+  let mut fonts = Vec::new();
+  for entry in entries {
+    let path = handle(entry, &format!("Reading entry in directory '{}'", dir))?.path();
+    let (id, ext) = match (
+      path.file_stem().and_then(|s| s.to_str()),
+      path.extension().and_then(|s| s.to_str()),
+    ) {
+      (Some(id), Some(ext)) => (id.to_string(), ext.to_ascii_lowercase()),
+      _ => continue,
+    };
+    if !is_valid_id(&id) || !FONT_EXTENSIONS.contains(&ext.as_str()) {
+      continue;
+    }
+
+    let (family, style) = read_font_names(&path).unwrap_or_else(|| names_from_id(&id));
+    fonts.push(Font { id, family, style });
+  }
+
+  fonts.sort_by(|a, b| a.id.cmp(&b.id));
+  Ok(fonts)
+}
+
+// 無法讀取name table時以 "Family-Style" 格式的檔名推測
+fn names_from_id(id: &str) -> (String, String) {
+  match id.rsplit_once('-') {
+    Some((family, style)) => (family.to_string(), style.to_string()),
+    None => (id.to_string(), String::from("Regular")),
+  }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+  Some(u16::from_be_bytes(
+    data.get(offset..offset + 2)?.try_into().ok()?,
+  ))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+  Some(u32::from_be_bytes(
+    data.get(offset..offset + 4)?.try_into().ok()?,
+  ))
+}
+
+// 讀取TrueType/OpenType name table的family(1)與subfamily(2)，TTC取第一個字型
+// 只讀取檔頭與name table，列出字型時不需讀入整個檔案
+pub fn read_font_names(path: &Path) -> Option<(String, String)> {
+  let mut file = File::open(path).ok()?;
+  let mut read = |offset: usize, length: usize| -> Option<Vec<u8>> {
+    let mut buf = vec![0; length];
+    file.seek(SeekFrom::Start(offset as u64)).ok()?;
+    file.read_exact(&mut buf).ok()?;
+    Some(buf)
+  };
+
+  let font_offset = match read(0, 4)?.as_slice() {
+    b"ttcf" => read_u32(&read(12, 4)?, 0)? as usize,
+    [0, 1, 0, 0] | b"OTTO" | b"true" => 0,
+    _ => return None,
+  };
+
+  let num_tables = read_u16(&read(font_offset + 4, 2)?, 0)? as usize;
+  let records = read(font_offset + 12, num_tables * 16)?;
+  let record = (0..num_tables)
+    .map(|i| i * 16)
+    .find(|record| records.get(*record..*record + 4) == Some(b"name"))?;
+  let name_offset = read_u32(&records, record + 8)? as usize;
+  let name_length = (read_u32(&records, record + 12)? as usize).min(MAX_NAME_TABLE_BYTES);
+  parse_name_table(&read(name_offset, name_length)?)
+}
+
+fn parse_name_table(data: &[u8]) -> Option<(String, String)> {
+  let count = read_u16(data, 2)? as usize;
+  let strings = read_u16(data, 4)? as usize;
+
+  let mut family = None;
+  let mut style = None;
+  for i in 0..count {
+    let record = 6 + i * 12;
+    let platform = read_u16(data, record)?;
+    let language = read_u16(data, record + 4)?;
+    let name_id = read_u16(data, record + 6)?;
+    let length = read_u16(data, record + 8)? as usize;
+    let offset = strings + read_u16(data, record + 10)? as usize;
+    let bytes = data.get(offset..offset + length)?;
+
+    // Windows平台為UTF-16BE，優先使用英文名稱
+    let (value, preferred) = match platform {
+      3 => {
+        let units: Vec<u16> = bytes
+          .chunks(2)
+          .filter_map(|c| c.try_into().ok().map(u16::from_be_bytes))
+          .collect();
+        (String::from_utf16(&units).ok()?, language == 0x0409)
+      }
+      1 => (bytes.iter().map(|b| *b as char).collect(), false),
+      _ => continue,
+    };
+
+    let slot = match name_id {
+      1 => &mut family,
+      2 => &mut style,
+      _ => continue,
+    };
+    if slot.is_none() || preferred {
+      *slot = Some(value);
+    }
+  }
+
+  Some((family?, style?))
+}
@@ -0,0 +1,231 @@
+use crate::{
+  database,
+  model::{
+    constant::*,
+    subtitle::{FormatOptions, ReadingSpeedWarning, Subtitle},
+  },
+  subs::*,
+  utils::*,
+};
+use std::fs;
+
+// 不能出現在行首的標點
+static CLOSING_PUNCTUATION: &'static str = "，。、！？；：）」』》〉】…,.!?;:)";
+
+struct Token {
+  text: String,
+  space: bool,
+}
+
+pub fn is_cjk(c: char) -> bool {
+  matches!(c,
+    '\u{1100}'..='\u{115F}'
+    | '\u{2E80}'..='\u{A4CF}'
+    | '\u{AC00}'..='\u{D7A3}'
+    | '\u{F900}'..='\u{FAFF}'
+    | '\u{FE30}'..='\u{FE4F}'
+    | '\u{FF00}'..='\u{FF60}'
+    | '\u{FFE0}'..='\u{FFE6}')
+}
+
+// CJK字寬度為2，其餘為1，空白不計
+pub fn text_width(text: &str) -> usize {
+  text
+    .chars()
+    .filter(|c| !c.is_whitespace())
+    .map(|c| if is_cjk(c) { 2 } else { 1 })
+    .sum()
+}
+
+// 英文以空白分詞，CJK每個字各自為一個詞，結尾標點跟著前一個詞
+fn tokenize(text: &str) -> Vec<Token> {
+  let mut tokens: Vec<Token> = Vec::new();
+  let mut current = String::new();
+  let mut space = false;
+  let mut current_space = false;
+
+  for c in text.chars() {
+    if c.is_whitespace() {
+      if !current.is_empty() {
+        tokens.push(Token {
+          text: std::mem::take(&mut current),
+          space: current_space,
+        });
+      }
+      space = true;
+      continue;
+    }
+
+    if is_cjk(c) || CLOSING_PUNCTUATION.contains(c) {
+      if !current.is_empty() {
+        tokens.push(Token {
+          text: std::mem::take(&mut current),
+          space: current_space,
+        });
+      }
+      match tokens.last_mut() {
+        Some(last) if !space && CLOSING_PUNCTUATION.contains(c) => last.text.push(c),
+        _ => tokens.push(Token {
+          text: c.to_string(),
+          space,
+        }),
+      }
+      space = false;
+      continue;
+    }
+
+    if current.is_empty() {
+      current_space = space;
+    }
+    current.push(c);
+    space = false;
+  }
+  if !current.is_empty() {
+    tokens.push(Token {
+      text: current,
+      space: current_space,
+    });
+  }
+  tokens
+}
+
+pub fn wrap_text(text: &str, max_width: usize) -> Vec<String> {
+  let mut lines = Vec::new();
+  let mut line = String::new();
+
+  for token in tokenize(text) {
+    let width = text_width(&line) + text_width(&token.text) + token.space as usize;
+    if !line.is_empty() && width > max_width {
+      lines.push(std::mem::take(&mut line));
+    }
+    if token.space && !line.is_empty() {
+      line.push(' ');
+    }
+    line.push_str(&token.text);
+  }
+  if !line.is_empty() {
+    lines.push(line);
+  }
+  lines
+}
+
+// 超過行數的字幕依字數比例切成多句，逐字時間依中點分配到各句
+fn split_cue(sub: &Subtitle, options: &FormatOptions) -> Vec<Subtitle> {
+  let lines = wrap_text(&sub.text, options.max_chars_per_line);
+  if lines.len() <= options.max_lines {
+    let mut cue = sub.clone();
+    cue.text = lines.join("\n");
+    return vec![cue];
+  }
+
+  let start = parse_time(&sub.start_time).unwrap_or(0);
+  let end = parse_time(&sub.end_time).unwrap_or(start).max(start);
+  let chunks: Vec<String> = lines
+    .chunks(options.max_lines)
+    .map(|chunk| chunk.join("\n"))
+    .collect();
+  let total = chunks.iter().map(|c| text_width(c)).sum::<usize>().max(1) as u64;
+
+  let mut cues = Vec::new();
+  let mut offset = 0;
+  let mut cursor = start;
+  for (i, chunk) in chunks.iter().enumerate() {
+    offset += text_width(chunk) as u64;
+    let chunk_end = match i == chunks.len() - 1 {
+      true => end,
+      false => start + (end - start) * offset / total,
+    };
+
+    let mut cue = sub.clone();
+    cue.text = chunk.clone();
+    cue.start_time = format_time(cursor, ',');
+    cue.end_time = format_time(chunk_end, ',');
+    cue.words = sub
+      .words
+      .iter()
+      .filter(|word| {
+        let middle =
+          (parse_time(&word.start_time).unwrap_or(0) + parse_time(&word.end_time).unwrap_or(0)) / 2;
+        (middle >= cursor || i == 0) && (middle < chunk_end || i == chunks.len() - 1)
+      })
+      .cloned()
+      .collect();
+    cues.push(cue);
+    cursor = chunk_end;
+  }
+  cues
+}
+
+pub fn format_subtitles(
+  subtitles: &[Subtitle],
+  options: &FormatOptions,
+) -> (Vec<Subtitle>, Vec<ReadingSpeedWarning>) {
+  let formatted: Vec<Subtitle> = subtitles
+    .iter()
+    .flat_map(|sub| split_cue(sub, options))
+    .collect();
+  let warnings = check_reading_speed(&formatted, options.max_cps);
+  (formatted, warnings)
+}
+
+pub fn check_reading_speed(subtitles: &[Subtitle], max_cps: f32) -> Vec<ReadingSpeedWarning> {
+  subtitles
+    .iter()
+    .enumerate()
+    .filter_map(|(index, sub)| {
+      let start = parse_time(&sub.start_time)?;
+      let end = parse_time(&sub.end_time)?;
+      let seconds = end.saturating_sub(start) as f32 / 1000.0;
+      let cps = match seconds > 0.0 {
+        true => text_width(&sub.text) as f32 / seconds,
+        false => f32::INFINITY,
+      };
+      match cps > max_cps {
+        true => Some(ReadingSpeedWarning {
+          index,
+          start_time: sub.start_time.clone(),
+          cps,
+        }),
+        false => None,
+      }
+    })
+    .collect()
+}
+
+// Whisper產生字幕後直接重新排版srt與逐字時間
+pub fn format_subtitle_file(code: &str, options: &FormatOptions) -> Result<(), Error> {
+  log::info!("Formatting subtitles for code: {}", code);
+
+  let subtitles = match load_subtitles(code)? {
+    Some(subtitles) => subtitles,
+    None => return Ok(()),
+  };
+  let (subtitles, warnings) = format_subtitles(&subtitles, options);
+  if !warnings.is_empty() {
+    log::warn!(
+      "{} subtitles exceed {} characters per second for code: {}",
+      warnings.len(),
+      options.max_cps,
+      code
+    );
+  }
+
+  let subs_path = handle(get_file_path(code, SUBS_FILE), "Getting subtitles file")?;
+  handle(
+    fs::write(&subs_path, to_srt(&subtitles)),
+    &format!("Writing file '{}'", subs_path),
+  )?;
+  if let Ok(words_path) = get_file_path(code, WORDS_FILE) {
+    handle(
+      fs::write(&words_path, to_words(&subtitles)?),
+      &format!("Writing file '{}'", words_path),
+    )?;
+  }
+  handle(
+    database::update_task_reading_speed_warnings(code, &warnings),
+    &format!("Updating reading speed warnings for code: {}", code),
+  )?;
+
+  log::info!("Formatting subtitles completed for code: {}", code);
+  Ok(())
+}
@@ -1,10 +1,22 @@
 mod api;
+mod auth;
+mod chapters;
 mod controller;
 mod database;
+mod export;
+mod fonts;
+mod formatter;
 mod logger;
 mod model;
+mod range;
+mod search;
+mod signer;
+mod subs;
 mod timer;
+mod translate;
+mod tts;
 mod utils;
+mod version;
 mod worker;
 
 #[cfg(test)]
@@ -37,6 +49,8 @@ impl Fairing for CORS {
       "POST, GET, PATCH, OPTIONS",
     ));
     response.set_header(Header::new("Access-Control-Allow-Headers", "*"));
+    // 前端需讀取ETag作為修改字幕時的If-Match
+    response.set_header(Header::new("Access-Control-Expose-Headers", "ETag"));
     response.set_header(Header::new("Access-Control-Allow-Credentials", "true"));
   }
 }
@@ -60,13 +74,23 @@ fn handle_code_undefind(_: &Request) -> &'static str {
 async fn main() {
   dotenv().ok();
   logger::init_logger(log::LevelFilter::Info);
+  if let Err(e) = signer::check_secret() {
+    log::error!("Failed to get download secret: {}", e);
+    panic!("Failed to get download secret");
+  }
   database::init_db();
+  if let Err(e) = fonts::migrate_default_font() {
+    log::warn!("Failed to migrate default font: {}", e);
+  }
 
   tokio::spawn(timer::start());
   let (mtx, mrx) = tokio::sync::mpsc::channel::<model::worker::MergeSubsRequest>(100);
   let (tx, rx) = tokio::sync::mpsc::channel::<model::worker::GenVideoRequest>(100);
-  tokio::spawn(worker::start_gen_video_worker(rx, mtx.clone()));
+  let (rtx, rrx) = tokio::sync::mpsc::channel::<model::worker::RerenderRequest>(100);
+  let tts = std::sync::Arc::new(tts::SidecarTts::new());
+  tokio::spawn(worker::start_gen_video_worker(rx, mtx.clone(), tts));
   tokio::spawn(worker::start_merge_subs_worker(mrx));
+  tokio::spawn(worker::start_rerender_worker(rrx));
 
   let server = rocket::build()
     .register(
@@ -82,16 +106,41 @@ async fn main() {
       routes![
         gen_video,
         set_email,
+        rerender,
+        render,
+        get_timeline,
+        set_timeline,
+        get_chapters,
+        set_chapters,
         check_task_status,
         download,
-        get_file_path_for_code,
-        set_subtitle
+        create_download_link,
+        list_artifacts,
+        get_transcript,
+        get_artifact,
+        download_bundle,
+        list_fonts,
+        upload_font,
+        get_subtitle,
+        set_subtitle,
+        shift_subtitle,
+        scale_subtitle,
+        list_revisions,
+        diff_revision,
+        restore_revision,
+        format_subtitle,
+        translate_subtitle,
+        list_translations,
+        get_translation,
+        search_subtitles
       ],
     )
     .attach(CORS)
+    .manage::<Box<dyn translate::Translator>>(Box::new(translate::SidecarTranslator::new()))
     .manage(model::worker::Sender {
       gen_sender: tx,
       merge_sender: mtx,
+      rerender_sender: rtx,
     })
     .launch();
 
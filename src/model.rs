@@ -1,6 +1,20 @@
+pub mod artifact;
+pub mod audio;
+pub mod avatar;
+pub mod chapter;
 pub mod constant;
 pub mod email;
+pub mod font;
+pub mod generation;
+pub mod link;
+pub mod revision;
+pub mod script;
+pub mod search;
+pub mod slideshow;
 pub mod subtitle;
 pub mod task;
+pub mod transcript;
+pub mod transcription;
+pub mod translation;
 pub mod video;
 pub mod worker;
@@ -0,0 +1,80 @@
+use crate::model::constant::*;
+use rocket::request::FromParam;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Artifact {
+  Result,
+  ResultWithSubtitles,
+  SubtitlesSrt,
+  SubtitlesVtt,
+  SubtitlesAss,
+  Transcript,
+  Chapters,
+  AvatarVideo,
+  AvatarImage,
+}
+
+pub static ARTIFACTS: [Artifact; 9] = [
+  Artifact::Result,
+  Artifact::ResultWithSubtitles,
+  Artifact::SubtitlesSrt,
+  Artifact::SubtitlesVtt,
+  Artifact::SubtitlesAss,
+  Artifact::Transcript,
+  Artifact::Chapters,
+  Artifact::AvatarVideo,
+  Artifact::AvatarImage,
+];
+
+impl Artifact {
+  pub fn name(&self) -> &'static str {
+    match self {
+      Artifact::Result => "result",
+      Artifact::ResultWithSubtitles => "result_with_subtitles",
+      Artifact::SubtitlesSrt => "subtitles_srt",
+      Artifact::SubtitlesVtt => "subtitles_vtt",
+      Artifact::SubtitlesAss => "subtitles_ass",
+      Artifact::Transcript => "transcript",
+      Artifact::Chapters => "chapters_vtt",
+      Artifact::AvatarVideo => "avatar",
+      Artifact::AvatarImage => "avatar_image",
+    }
+  }
+
+  pub fn file(&self) -> &'static str {
+    match self {
+      Artifact::Result => RESULT_FILE,
+      Artifact::ResultWithSubtitles => RESULT_WITH_SUBS_FILE,
+      Artifact::SubtitlesSrt => SUBS_EXPORT_FILE,
+      Artifact::SubtitlesVtt => SUBS_VTT_FILE,
+      Artifact::SubtitlesAss => SUBS_ASS_FILE,
+      Artifact::Transcript => TRANSCRIPT_FILE,
+      Artifact::Chapters => CHAPTERS_FILE,
+      Artifact::AvatarVideo => AVATAR_VIDEO_FILE,
+      Artifact::AvatarImage => BG_AVATAR_FILE,
+    }
+  }
+
+  // 由字幕產生的檔案，每次下載前重新輸出
+  pub fn is_derived(&self) -> bool {
+    matches!(
+      self,
+      Artifact::SubtitlesSrt
+        | Artifact::SubtitlesVtt
+        | Artifact::SubtitlesAss
+        | Artifact::Transcript
+    )
+  }
+
+  pub fn from_name(name: &str) -> Option<Artifact> {
+    ARTIFACTS.iter().find(|a| a.name() == name).copied()
+  }
+}
+
+impl<'a> FromParam<'a> for Artifact {
+  type Error = &'a str;
+
+  fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+    Artifact::from_name(param).ok_or(param)
+  }
+}
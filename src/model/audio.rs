@@ -0,0 +1,42 @@
+use rocket::{
+  form::{self, Error},
+  FromForm, FromFormField,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(FromFormField, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NoiseReduction {
+  #[field(value = "off")]
+  Off,
+  #[field(value = "light")]
+  Light,
+  #[field(value = "strong")]
+  Strong,
+}
+
+// 音訊後處理參數，響度標準化依EBU R128
+#[derive(FromForm, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AudioOptions {
+  #[field(default = false)]
+  pub normalize: bool,
+  #[field(default = -16.0, validate = validate_loudness())]
+  pub target_lufs: f32,
+  #[field(default = NoiseReduction::Off)]
+  pub noise_reduction: NoiseReduction,
+}
+
+impl AudioOptions {
+  pub fn is_enabled(&self) -> bool {
+    self.normalize || self.noise_reduction != NoiseReduction::Off
+  }
+}
+
+fn validate_loudness<'a>(value: &f32) -> form::Result<'a, ()> {
+  if *value >= -70.0 && *value <= -5.0 {
+    Ok(())
+  } else {
+    log::warn!("The target loudness must be between -70 and -5 LUFS");
+    Err(Error::validation("The target loudness must be between -70 and -5 LUFS").into())
+  }
+}
@@ -0,0 +1,212 @@
+use crate::{
+  model::constant::{BACKGROUND_JPG_FILE, BACKGROUND_PNG_FILE},
+  subs::parse_time,
+};
+use rocket::{
+  form::{self, Error},
+  http::ContentType,
+  FromForm, FromFormField,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(FromFormField, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Shape {
+  #[field(value = "circle")]
+  Circle,
+  #[field(value = "square")]
+  Square,
+  #[field(value = "rounded_rectangle")]
+  RoundedRectangle,
+  #[field(value = "original")]
+  Original,
+}
+
+// x、y所對應的頭像角落
+#[derive(FromFormField, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Anchor {
+  #[field(value = "top_left")]
+  TopLeft,
+  #[field(value = "top_right")]
+  TopRight,
+  #[field(value = "bottom_left")]
+  BottomLeft,
+  #[field(value = "bottom_right")]
+  BottomRight,
+}
+
+// 去背後頭像的背景處理方式
+#[derive(FromFormField, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BackgroundMode {
+  #[field(value = "transparent")]
+  Transparent,
+  #[field(value = "color")]
+  Color,
+  #[field(value = "blur")]
+  Blur,
+  #[field(value = "image")]
+  Image,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Background {
+  pub mode: BackgroundMode,
+  pub color: String,
+  // 上傳的背景圖片檔名，依圖片格式保留副檔名
+  pub image: Option<String>,
+}
+
+pub fn background_file(content_type: Option<&ContentType>) -> &'static str {
+  match content_type {
+    Some(content_type) if content_type == &ContentType::JPEG => BACKGROUND_JPG_FILE,
+    _ => BACKGROUND_PNG_FILE,
+  }
+}
+
+#[derive(FromForm, Serialize, Deserialize, Debug, Clone)]
+pub struct Layout {
+  #[field(validate = validate_range_0_to_1())]
+  pub x: f32,
+  #[field(validate = validate_range_0_to_1())]
+  pub y: f32,
+  pub shape: Shape,
+  #[field(default = "#FFFFFF", validate = validate_color())]
+  pub border_color: String,
+  #[field(default = 0, validate = range(0..=20))]
+  pub border_width: u32,
+  // 頭像高度佔影片高度的比例，未設定時由合成端決定
+  #[field(validate = validate_scale())]
+  pub scale: Option<f32>,
+  #[field(default = Anchor::TopLeft)]
+  pub anchor: Anchor,
+  #[field(default = 0.0, validate = validate_margin())]
+  pub margin_x: f32,
+  #[field(default = 0.0, validate = validate_margin())]
+  pub margin_y: f32,
+}
+
+pub fn validate_range_0_to_1<'a>(value: &f32) -> form::Result<'a, ()> {
+  if *value >= 0.0 && *value <= 1.0 {
+    Ok(())
+  } else {
+    log::warn!("The value must be between 0 and 1");
+    Err(Error::validation("The value must be between 0 and 1").into())
+  }
+}
+
+pub fn validate_color<'a>(value: &str) -> form::Result<'a, ()> {
+  let hex = value.strip_prefix('#').unwrap_or("");
+  if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+    Ok(())
+  } else {
+    log::warn!("Invalid color: {}", value);
+    Err(Error::validation("Color must be in #RRGGBB format").into())
+  }
+}
+
+pub fn validate_scale<'a>(value: &Option<f32>) -> form::Result<'a, ()> {
+  match value {
+    Some(scale) if *scale < 0.05 || *scale > 1.0 => {
+      log::warn!("The scale must be between 0.05 and 1");
+      Err(Error::validation("The scale must be between 0.05 and 1").into())
+    }
+    _ => Ok(()),
+  }
+}
+
+pub fn validate_margin<'a>(value: &f32) -> form::Result<'a, ()> {
+  if *value >= 0.0 && *value <= 0.5 {
+    Ok(())
+  } else {
+    log::warn!("The margin must be between 0 and 0.5");
+    Err(Error::validation("The margin must be between 0 and 0.5").into())
+  }
+}
+
+#[derive(FromForm, Serialize, Deserialize, Debug, Clone)]
+pub struct Keyframe {
+  #[field(validate = validate_time())]
+  pub start_time: String,
+  #[field(validate = validate_time())]
+  pub end_time: String,
+  #[field(validate = validate_range_0_to_1())]
+  pub x: f32,
+  #[field(validate = validate_range_0_to_1())]
+  pub y: f32,
+  #[field(validate = validate_scale())]
+  pub scale: Option<f32>,
+  #[field(default = true)]
+  pub visible: bool,
+}
+
+#[derive(FromForm, Debug)]
+pub struct TimelineRequest {
+  #[field(validate = validate_timeline())]
+  pub timeline: Vec<Keyframe>,
+}
+
+pub fn validate_time<'a>(time: &str) -> form::Result<'a, ()> {
+  match parse_time(time) {
+    Some(_) => Ok(()),
+    None => Err(Error::validation("Incorrect Time Format").into()),
+  }
+}
+
+pub fn validate_timeline<'a>(timeline: &[Keyframe]) -> form::Result<'a, ()> {
+  match check_timeline(timeline, None) {
+    Ok(()) => Ok(()),
+    Err(e) => {
+      log::warn!("Invalid timeline: {}", e);
+      Err(Error::validation(e).into())
+    }
+  }
+}
+
+// 檢查區段順序不重疊，並可選擇檢查是否超過影片長度（毫秒）
+pub fn check_timeline(timeline: &[Keyframe], duration: Option<u64>) -> Result<(), String> {
+  check_intervals(
+    timeline
+      .iter()
+      .map(|keyframe| (keyframe.start_time.as_str(), keyframe.end_time.as_str())),
+    "Keyframe",
+    duration,
+  )
+}
+
+// 時間區段依序且不重疊，label用於錯誤訊息（Keyframe、Chapter）
+pub fn check_intervals<'a>(
+  intervals: impl IntoIterator<Item = (&'a str, &'a str)>,
+  label: &str,
+  duration: Option<u64>,
+) -> Result<(), String> {
+  let mut last_end = 0;
+
+  for (i, (start_time, end_time)) in intervals.into_iter().enumerate() {
+    let (start, end) = match (parse_time(start_time), parse_time(end_time)) {
+      (Some(start), Some(end)) => (start, end),
+      (_, _) => return Err(format!("{} {} has incorrect time format", label, i)),
+    };
+
+    if start >= end {
+      return Err(format!("{} {} ends before it starts", label, i));
+    }
+    if i > 0 && start < last_end {
+      return Err(format!(
+        "{} {} overlaps the previous {}",
+        label,
+        i,
+        label.to_lowercase()
+      ));
+    }
+    if let Some(duration) = duration {
+      if end > duration {
+        return Err(format!("{} {} exceeds the video duration", label, i));
+      }
+    }
+    last_end = end;
+  }
+
+  Ok(())
+}
@@ -0,0 +1,63 @@
+use crate::model::avatar::{check_intervals, validate_time};
+use rocket::{
+  form::{self, Error},
+  FromForm,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(FromForm, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Chapter {
+  #[field(validate = validate_time())]
+  pub start_time: String,
+  #[field(validate = validate_time())]
+  pub end_time: String,
+  #[field(validate = len(1..=200))]
+  pub title: String,
+}
+
+#[derive(FromForm, Debug)]
+pub struct Request {
+  #[field(validate = validate_chapters())]
+  pub chapters: Vec<Chapter>,
+}
+
+// 以場景偵測自動產生章節，threshold越小越容易切出新章節
+#[derive(FromForm, Serialize, Deserialize, Debug, Clone)]
+pub struct ChapterOptions {
+  #[field(default = false)]
+  pub enabled: bool,
+  #[field(default = 0.3, validate = validate_threshold())]
+  pub threshold: f32,
+  // 章節最短秒數，避免動畫或翻頁效果切出過多章節
+  #[field(default = 30, validate = range(1..=3600))]
+  pub min_duration: u32,
+}
+
+fn validate_threshold<'v>(threshold: &f32) -> form::Result<'v, ()> {
+  if *threshold > 0.0 && *threshold <= 1.0 {
+    Ok(())
+  } else {
+    Err(Error::validation("The threshold must be between 0 and 1").into())
+  }
+}
+
+pub fn validate_chapters<'a>(chapters: &[Chapter]) -> form::Result<'a, ()> {
+  match check_chapters(chapters, None) {
+    Ok(()) => Ok(()),
+    Err(e) => {
+      log::warn!("Invalid chapters: {}", e);
+      Err(Error::validation(e).into())
+    }
+  }
+}
+
+// 檢查章節依序且不重疊，並可選擇檢查是否超過影片長度（毫秒）
+pub fn check_chapters(chapters: &[Chapter], duration: Option<u64>) -> Result<(), String> {
+  check_intervals(
+    chapters
+      .iter()
+      .map(|chapter| (chapter.start_time.as_str(), chapter.end_time.as_str())),
+    "Chapter",
+    duration,
+  )
+}
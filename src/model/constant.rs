@@ -1,9 +1,42 @@
 pub static VIDEO_FILE: &'static str = "video.mp4";
 pub static AUDIO_FILE: &'static str = "audio.wav";
+pub static SOURCE_VIDEO_FILE: &'static str = "source_video.mp4";
+pub static SOURCE_AUDIO_FILE: &'static str = "source_audio.wav";
+pub static NARRATION_WAV_FILE: &'static str = "narration.wav";
+pub static NARRATION_MP3_FILE: &'static str = "narration.mp3";
+pub static NARRATION_M4A_FILE: &'static str = "narration.m4a";
+pub static NARRATION_FILES: [&'static str; 3] =
+  [NARRATION_WAV_FILE, NARRATION_MP3_FILE, NARRATION_M4A_FILE];
+pub static SCRIPT_FILE: &'static str = "script.txt";
 pub static AVATAR_FILE: &'static str = "avatar.jpg";
 pub static GEN_DIR: &'static str = "gen";
+pub static SLIDES_DIR: &'static str = "slides";
+pub static SLIDES_PDF_FILE: &'static str = "slides.pdf";
 pub static AVATAR_VIDEO_FILE: &'static str = "avatar_video.mp4";
 pub static RESULT_FILE: &'static str = "result.mp4";
 pub static SUBS_FILE: &'static str = "subs.srt";
 pub static RESULT_WITH_SUBS_FILE: &'static str = "result_with_subs.mp4";
 pub static DEBG_AVATAR_FILE: &'static str = "debg_avatar.png";
+pub static BACKGROUND_PNG_FILE: &'static str = "background.png";
+pub static BACKGROUND_JPG_FILE: &'static str = "background.jpg";
+pub static BG_AVATAR_FILE: &'static str = "bg_avatar.png";
+pub static SUBS_EXPORT_FILE: &'static str = "subtitles.srt";
+pub static SUBS_VTT_FILE: &'static str = "subtitles.vtt";
+pub static SUBS_ASS_FILE: &'static str = "subtitles.ass";
+pub static WORDS_FILE: &'static str = "words.json";
+pub static TRANSCRIPT_FILE: &'static str = "transcript.txt";
+pub static CHAPTERS_FILE: &'static str = "chapters.vtt";
+pub static BUNDLE_FILE: &'static str = "bundle.zip";
+pub static BUNDLE_KEY_FILE: &'static str = "bundle.key";
+
+// 簽章連結可使用的資源，其餘為artifact名稱
+pub static DOWNLOAD_RESOURCE: &'static str = "download";
+pub static BUNDLE_RESOURCE: &'static str = "bundle";
+// 單次連結使用後，同一個IP可續傳的秒數
+pub static DOWNLOAD_CONTINUATION_SECONDS: i64 = 1800;
+pub static DEFAULT_BASE_URL: &'static str = "http://localhost:8000";
+pub static EMAIL_LINK_TTL: i64 = 7 * 24 * 60 * 60;
+pub static DEFAULT_FONT: &'static str = "NotoSansCJK-Regular";
+// 舊版預設字型放在工作目錄，啟動時會複製到$ROOT/fonts
+pub static LEGACY_DEFAULT_FONT_PATH: &'static str = "./NotoSansCJK-Regular.ttc";
+pub static DEFAULT_FONT_FAMILY: &'static str = "Noto Sans CJK TC";
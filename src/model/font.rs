@@ -0,0 +1,14 @@
+use rocket::{fs::TempFile, FromForm};
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Font {
+  pub id: String,
+  pub family: String,
+  pub style: String,
+}
+
+#[derive(FromForm, Debug)]
+pub struct Upload<'a> {
+  pub file: TempFile<'a>,
+}
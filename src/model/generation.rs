@@ -0,0 +1,201 @@
+use rocket::{
+  form::{self, DataField, Error, Options, ValueField},
+  FromForm, FromFormField,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(FromFormField, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Preprocess {
+  #[field(value = "crop")]
+  Crop,
+  #[field(value = "resize")]
+  Resize,
+  #[field(value = "full")]
+  Full,
+  #[field(value = "extcrop")]
+  Extcrop,
+  #[field(value = "extfull")]
+  Extfull,
+}
+
+// SadTalker生成參數
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GenerationOptions {
+  pub preprocess: Preprocess,
+  pub still_mode: bool,
+  pub face_enhancer: bool,
+  pub pose_style: u32,
+  pub expression_scale: f32,
+  pub size: u32,
+}
+
+// 表單中的生成參數，未指定的欄位依序使用preset與預設值
+#[derive(FromForm, Debug, Clone, Default, PartialEq)]
+pub struct GenerationRequest {
+  pub preprocess: Explicit<Preprocess>,
+  pub still_mode: Explicit<bool>,
+  pub face_enhancer: Explicit<bool>,
+  #[field(validate = validate_pose_style())]
+  pub pose_style: Explicit<u32>,
+  #[field(validate = validate_expression_scale())]
+  pub expression_scale: Explicit<f32>,
+  #[field(validate = validate_size())]
+  pub size: Explicit<u32>,
+}
+
+// Option<T>解析失敗時也是None，這裡只有未填寫時為None，填寫錯誤時回傳錯誤
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Explicit<T>(pub Option<T>);
+
+impl<T> Default for Explicit<T> {
+  fn default() -> Self {
+    Explicit(None)
+  }
+}
+
+#[rocket::async_trait]
+impl<'v, T: form::FromForm<'v>> form::FromForm<'v> for Explicit<T> {
+  type Context = (T::Context, bool);
+
+  fn init(opts: Options) -> Self::Context {
+    (T::init(opts), false)
+  }
+
+  fn push_value(ctxt: &mut Self::Context, field: ValueField<'v>) {
+    ctxt.1 = true;
+    T::push_value(&mut ctxt.0, field)
+  }
+
+  async fn push_data(ctxt: &mut Self::Context, field: DataField<'v, '_>) {
+    ctxt.1 = true;
+    T::push_data(&mut ctxt.0, field).await
+  }
+
+  fn finalize((ctxt, present): Self::Context) -> form::Result<'v, Self> {
+    match present {
+      true => T::finalize(ctxt).map(|value| Explicit(Some(value))),
+      false => Ok(Explicit(None)),
+    }
+  }
+}
+
+impl GenerationRequest {
+  pub fn resolve(&self, preset: Option<Preset>) -> GenerationOptions {
+    let base = preset.unwrap_or(Preset::Standard).generation();
+    GenerationOptions {
+      preprocess: self.preprocess.0.unwrap_or(base.preprocess),
+      still_mode: self.still_mode.0.unwrap_or(base.still_mode),
+      face_enhancer: self.face_enhancer.0.unwrap_or(base.face_enhancer),
+      pose_style: self.pose_style.0.unwrap_or(base.pose_style),
+      expression_scale: self.expression_scale.0.unwrap_or(base.expression_scale),
+      size: self.size.0.unwrap_or(base.size),
+    }
+  }
+}
+
+impl Default for GenerationOptions {
+  fn default() -> Self {
+    GenerationOptions {
+      preprocess: Preprocess::Crop,
+      still_mode: false,
+      face_enhancer: false,
+      pose_style: 0,
+      expression_scale: 1.0,
+      size: 256,
+    }
+  }
+}
+
+fn validate_pose_style<'a>(value: &Explicit<u32>) -> form::Result<'a, ()> {
+  match value.0 {
+    Some(value) if value > 45 => {
+      log::warn!("The pose style must be between 0 and 45");
+      Err(Error::validation("The pose style must be between 0 and 45").into())
+    }
+    _ => Ok(()),
+  }
+}
+
+fn validate_expression_scale<'a>(value: &Explicit<f32>) -> form::Result<'a, ()> {
+  match value.0 {
+    Some(value) if !(0.0..=3.0).contains(&value) => {
+      log::warn!("The expression scale must be between 0 and 3");
+      Err(Error::validation("The expression scale must be between 0 and 3").into())
+    }
+    _ => Ok(()),
+  }
+}
+
+fn validate_size<'a>(value: &Explicit<u32>) -> form::Result<'a, ()> {
+  match value.0 {
+    None | Some(256) | Some(512) => Ok(()),
+    _ => {
+      log::warn!("The size must be 256 or 512");
+      Err(Error::validation("The size must be 256 or 512").into())
+    }
+  }
+}
+
+#[derive(FromFormField, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Preset {
+  #[field(value = "draft")]
+  Draft,
+  #[field(value = "standard")]
+  Standard,
+  #[field(value = "high")]
+  High,
+}
+
+// FFmpeg輸出編碼參數
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EncodingOptions {
+  pub crf: u32,
+  pub speed: String,
+  pub height: Option<u32>,
+}
+
+impl Preset {
+  pub fn generation(&self) -> GenerationOptions {
+    match self {
+      Preset::Draft => GenerationOptions {
+        preprocess: Preprocess::Crop,
+        still_mode: true,
+        face_enhancer: false,
+        pose_style: 0,
+        expression_scale: 1.0,
+        size: 256,
+      },
+      Preset::Standard => GenerationOptions::default(),
+      Preset::High => GenerationOptions {
+        preprocess: Preprocess::Full,
+        still_mode: false,
+        face_enhancer: true,
+        pose_style: 0,
+        expression_scale: 1.0,
+        size: 512,
+      },
+    }
+  }
+
+  pub fn encoding(&self) -> EncodingOptions {
+    match self {
+      Preset::Draft => EncodingOptions {
+        crf: 32,
+        speed: "ultrafast".to_string(),
+        height: Some(480),
+      },
+      Preset::Standard => EncodingOptions {
+        crf: 23,
+        speed: "medium".to_string(),
+        height: None,
+      },
+      Preset::High => EncodingOptions {
+        crf: 18,
+        speed: "slow".to_string(),
+        height: None,
+      },
+    }
+  }
+}
@@ -0,0 +1,61 @@
+use crate::model::{
+  artifact::Artifact,
+  constant::{BUNDLE_RESOURCE, DOWNLOAD_RESOURCE},
+};
+use rocket::{
+  form::{self, Error},
+  request::{FromRequest, Outcome},
+  FromForm, Request as HttpRequest,
+};
+use std::convert::Infallible;
+
+#[derive(FromForm, Debug)]
+pub struct Request {
+  // 連結有效秒數（1分鐘 ~ 7天）
+  #[field(default = 3600, validate = range(60..=604800))]
+  pub ttl: i64,
+  #[field(default = false)]
+  pub single_use: bool,
+  // 連結可下載的資源：download、bundle或artifact名稱
+  #[field(default = DOWNLOAD_RESOURCE, validate = validate_resource())]
+  pub resource: String,
+}
+
+fn validate_resource<'v>(resource: &str) -> form::Result<'v, ()> {
+  if resource == DOWNLOAD_RESOURCE
+    || resource == BUNDLE_RESOURCE
+    || Artifact::from_name(resource).is_some()
+  {
+    Ok(())
+  } else {
+    Err(Error::validation("Unknown resource").into())
+  }
+}
+
+#[derive(FromForm, Debug)]
+pub struct Signature {
+  pub expires: Option<i64>,
+  pub nonce: Option<String>,
+  pub signature: Option<String>,
+}
+
+// 單次連結判斷是否為同一個使用者的後續Range請求
+#[derive(Debug)]
+pub struct Client {
+  pub ip: Option<String>,
+  pub range: Option<String>,
+  pub if_range: Option<String>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Client {
+  type Error = Infallible;
+
+  async fn from_request(req: &'r HttpRequest<'_>) -> Outcome<Self, Self::Error> {
+    Outcome::Success(Client {
+      ip: req.client_ip().map(|ip| ip.to_string()),
+      range: req.headers().get_one("Range").map(String::from),
+      if_range: req.headers().get_one("If-Range").map(String::from),
+    })
+  }
+}
@@ -0,0 +1,49 @@
+use crate::model::subtitle::Subtitle;
+use rocket::{
+  form::{self, Error},
+  FromForm,
+};
+use serde::Serialize;
+
+// 第0版為Whisper產生的字幕
+pub static ORIGINAL_REVISION: u32 = 0;
+pub static ORIGINAL_AUTHOR: &'static str = "whisper";
+
+#[derive(Serialize, Debug)]
+pub struct Revision {
+  pub revision: u32,
+  pub author: String,
+  pub created_at: String,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+  Added,
+  Removed,
+}
+
+// 修改過的字幕以刪除舊的、新增新的表示，index為各自版本中的位置
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Change {
+  pub op: Operation,
+  pub index: usize,
+  pub cue: Subtitle,
+}
+
+#[derive(FromForm, Debug)]
+pub struct RestoreRequest {
+  #[field(validate = validate_author())]
+  pub author: Option<String>,
+}
+
+// 未填寫時以anonymous記錄，填寫時限制長度
+pub fn validate_author<'v>(author: &Option<String>) -> form::Result<'v, ()> {
+  match author {
+    Some(author) if author.is_empty() || author.chars().count() > 64 => {
+      log::warn!("Invalid author: {}", author);
+      Err(Error::validation("Author must be 1 to 64 characters").into())
+    }
+    _ => Ok(()),
+  }
+}
@@ -0,0 +1,63 @@
+use crate::subs::parse_time;
+use rocket::form::{self, Error};
+use serde::{Deserialize, Serialize};
+
+// 旁白稿的一段，start_time為毫秒
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScriptSegment {
+  pub text: String,
+  pub start_time: Option<u64>,
+}
+
+// 每行一段，可在行首加上 [HH:MM:SS] 或 [HH:MM:SS,mmm] 指定開始時間
+pub fn parse_script(script: &str) -> Result<Vec<ScriptSegment>, String> {
+  let mut segments = Vec::new();
+  let mut last_start = None;
+
+  for (i, line) in script.lines().enumerate() {
+    let line = line.trim();
+    if line.is_empty() {
+      continue;
+    }
+
+    let (start_time, text) = match line.strip_prefix('[').and_then(|l| l.split_once(']')) {
+      Some((time, text)) => match parse_time(time) {
+        Some(time) => (Some(time), text.trim()),
+        None => return Err(format!("Line {} has incorrect time format", i + 1)),
+      },
+      None => (None, line),
+    };
+
+    if text.is_empty() {
+      return Err(format!("Line {} has no text", i + 1));
+    }
+    if let (Some(start), Some(last)) = (start_time, last_start) {
+      if start < last {
+        return Err(format!("Line {} starts before the previous line", i + 1));
+      }
+    }
+    if start_time.is_some() {
+      last_start = start_time;
+    }
+
+    segments.push(ScriptSegment {
+      text: text.to_string(),
+      start_time,
+    });
+  }
+
+  if segments.is_empty() {
+    return Err(String::from("Script is empty"));
+  }
+  Ok(segments)
+}
+
+pub fn validate_script<'a>(script: &Option<String>) -> form::Result<'a, ()> {
+  match script.as_deref().map(parse_script) {
+    Some(Err(e)) => {
+      log::warn!("Invalid script: {}", e);
+      Err(Error::validation(e).into())
+    }
+    _ => Ok(()),
+  }
+}
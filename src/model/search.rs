@@ -0,0 +1,29 @@
+use rocket::FromForm;
+use serde::Serialize;
+
+// 只搜尋呼叫者持有的任務代碼
+#[derive(FromForm, Debug)]
+pub struct Query {
+  #[field(validate = len(1..=200))]
+  pub q: String,
+  #[field(validate = len(1..=100))]
+  pub code: Vec<String>,
+  // 依相關度排序後分頁，常見的詞也只回傳limit筆
+  #[field(default = 50, validate = range(1..=200))]
+  pub limit: u32,
+  #[field(default = 0, validate = range(0..=10000))]
+  pub offset: u32,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Hit {
+  pub start_time: String,
+  pub end_time: String,
+  pub snippet: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TaskHits {
+  pub code: String,
+  pub matches: Vec<Hit>,
+}
@@ -0,0 +1,49 @@
+use rocket::form::{self, Error};
+use serde::{Deserialize, Serialize};
+
+// 投影片影片的設定，durations與timestamps單位為秒
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Slideshow {
+  pub pdf: bool,
+  pub slides: Vec<String>,
+  pub durations: Vec<f32>,
+  pub timestamps: Vec<f32>,
+}
+
+impl Slideshow {
+  // 指定每張投影片的長度時可得知總長度(毫秒)
+  pub fn total_duration(&self) -> Option<u64> {
+    match self.durations.is_empty() {
+      true => None,
+      false => Some(
+        self
+          .durations
+          .iter()
+          .map(|d| (*d as f64 * 1000.0) as u64)
+          .sum(),
+      ),
+    }
+  }
+}
+
+pub fn validate_durations<'a>(durations: &[f32]) -> form::Result<'a, ()> {
+  if durations.iter().any(|d| !(*d > 0.0 && *d <= 3600.0)) {
+    log::warn!("Slide duration must be between 0 and 3600 seconds");
+    return Err(Error::validation("Slide duration must be between 0 and 3600 seconds").into());
+  }
+  Ok(())
+}
+
+pub fn validate_timestamps<'a>(timestamps: &[f32]) -> form::Result<'a, ()> {
+  if let Some(first) = timestamps.first() {
+    if *first != 0.0 {
+      log::warn!("First slide must start at 0");
+      return Err(Error::validation("First slide must start at 0").into());
+    }
+  }
+  if timestamps.windows(2).any(|w| w[1] <= w[0] || w[1].is_nan()) {
+    log::warn!("Slide timestamps must be increasing");
+    return Err(Error::validation("Slide timestamps must be increasing").into());
+  }
+  Ok(())
+}
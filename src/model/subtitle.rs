@@ -1,11 +1,15 @@
-use chrono::NaiveTime;
+use crate::{
+  fonts,
+  // 與平移、拉伸時使用的parse_time一致
+  model::{avatar::validate_time, constant::DEFAULT_FONT, revision::validate_author},
+};
 use rocket::{
   form::{self, Error},
   FromForm,
 };
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, FromForm, Serialize, Deserialize)]
+#[derive(Debug, FromForm, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Subtitle {
   #[field(validate = len(1..))]
   pub text: String,
@@ -13,28 +17,126 @@ pub struct Subtitle {
   pub fontsize: u32,
   #[field(default = "white")]
   pub color: String,
-  #[field(default = "./NotoSansCJK-Regular.ttc")]
+  // 已註冊的字型ID，見GET /api/fonts
+  #[field(default = DEFAULT_FONT, validate = validate_font())]
   pub font: String,
   #[field(validate = validate_time())]
   pub start_time: String,
   #[field(validate = validate_time())]
   pub end_time: String,
+  // 逐字時間，Whisper有提供時才會有
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub words: Vec<Word>,
+}
+
+#[derive(Debug, FromForm, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Word {
+  #[field(validate = len(1..))]
+  pub text: String,
+  #[field(validate = validate_time())]
+  pub start_time: String,
+  #[field(validate = validate_time())]
+  pub end_time: String,
+}
+
+impl Subtitle {
+  pub fn new(text: &str, start_time: &str, end_time: &str) -> Subtitle {
+    Subtitle {
+      text: text.to_string(),
+      fontsize: 32,
+      color: "white".to_string(),
+      font: DEFAULT_FONT.to_string(),
+      start_time: start_time.to_string(),
+      end_time: end_time.to_string(),
+      words: Vec::new(),
+    }
+  }
 }
 
 #[derive(Debug, FromForm, Serialize, Deserialize)]
 pub struct Request {
   pub subtitles: Vec<Subtitle>,
+  #[field(validate = validate_author())]
+  pub author: Option<String>,
+}
+
+// 平移字幕時間，設定from/to時只平移開始時間在範圍內的字幕
+#[derive(Debug, FromForm)]
+pub struct ShiftRequest {
+  // 毫秒，最多平移24小時
+  #[field(validate = range(-86_400_000..=86_400_000))]
+  pub offset: i64,
+  #[field(validate = validate_optional_time())]
+  pub from: Option<String>,
+  #[field(validate = validate_optional_time())]
+  pub to: Option<String>,
+  #[field(validate = validate_author())]
+  pub author: Option<String>,
+}
+
+// 以兩個同步點線性拉伸字幕時間，source為原本時間，target為對齊後時間
+#[derive(Debug, FromForm)]
+pub struct ScaleRequest {
+  #[field(validate = validate_time())]
+  pub source_start: String,
+  #[field(validate = validate_time())]
+  pub target_start: String,
+  #[field(validate = validate_time())]
+  pub source_end: String,
+  #[field(validate = validate_time())]
+  pub target_end: String,
+  #[field(validate = validate_author())]
+  pub author: Option<String>,
+}
+
+// 字幕斷行與閱讀速度設定，CJK字以2個字元寬計算
+#[derive(Debug, FromForm, Serialize, Deserialize, Clone)]
+pub struct FormatOptions {
+  #[field(default = true)]
+  pub enabled: bool,
+  #[field(default = 42, validate = range(8..=100))]
+  pub max_chars_per_line: usize,
+  #[field(default = 2, validate = range(1..=3))]
+  pub max_lines: usize,
+  #[field(default = 17.0, validate = validate_cps())]
+  pub max_cps: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ReadingSpeedWarning {
+  pub index: usize,
+  pub start_time: String,
+  pub cps: f32,
+}
+
+fn validate_font<'v>(font: &str) -> form::Result<'v, ()> {
+  match fonts::is_registered(font) {
+    true => Ok(()),
+    false => {
+      log::warn!("Font '{}' is not registered", font);
+      Err(Error::validation("Font is not registered").into())
+    }
+  }
+}
+
+fn validate_optional_time<'v>(time: &Option<String>) -> form::Result<'v, ()> {
+  match time {
+    Some(time) => validate_time(time),
+    None => Ok(()),
+  }
 }
 
-fn validate_time<'v>(time: &str) -> form::Result<'v, ()> {
-  match NaiveTime::parse_from_str(&time.replace(",", "."), &"%H:%M:%S%.f") {
-    Ok(_) => Ok(()),
-    Err(_) => Err(Error::validation("Incorrect Time Format").into()),
+fn validate_cps<'v>(cps: &f32) -> form::Result<'v, ()> {
+  if *cps > 0.0 && *cps <= 60.0 {
+    Ok(())
+  } else {
+    Err(Error::validation("The characters per second must be between 0 and 60").into())
   }
 }
 
 #[test]
 fn test_time_validate() {
+  use chrono::NaiveTime;
   let test1 = "00:00:10,500";
   let test2 = "00:00:10";
   let test3 = "";
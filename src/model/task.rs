@@ -6,6 +6,8 @@ pub struct Task {
   pub status: Status,
   pub subs_status: Status,
   pub video_status: Status,
+  pub keep_files: bool,
+  pub karaoke: bool,
 }
 
 #[derive(Debug)]
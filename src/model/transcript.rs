@@ -0,0 +1,31 @@
+use rocket::{FromForm, FromFormField};
+
+#[derive(FromFormField, Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+  #[field(value = "txt")]
+  Txt,
+  #[field(value = "md")]
+  Md,
+}
+
+// 字幕間隔小於pause毫秒時併入同一段落
+#[derive(FromForm, Debug)]
+pub struct Query {
+  #[field(default = Format::Txt)]
+  pub format: Format,
+  #[field(default = 2000, validate = range(0..=60000))]
+  pub pause: u64,
+  #[field(default = true)]
+  pub timestamps: bool,
+}
+
+// 匯出的transcript檔案使用預設選項
+impl Default for Query {
+  fn default() -> Self {
+    Query {
+      format: Format::Txt,
+      pause: 2000,
+      timestamps: true,
+    }
+  }
+}
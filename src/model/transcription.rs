@@ -0,0 +1,57 @@
+use rocket::{
+  form::{self, Error},
+  FromForm, FromFormField,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(FromFormField, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WhisperModel {
+  #[field(value = "tiny")]
+  Tiny,
+  #[field(value = "base")]
+  Base,
+  #[field(value = "small")]
+  Small,
+  #[field(value = "medium")]
+  Medium,
+  #[field(value = "large")]
+  Large,
+}
+
+// Whisper辨識參數，language為Whisper的語言代碼(如zh、en、yue)，未設定時由Whisper自動判斷
+#[derive(FromForm, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TranscriptionOptions {
+  #[field(validate = validate_language())]
+  pub language: Option<String>,
+  #[field(default = WhisperModel::Base)]
+  pub model: WhisperModel,
+  #[field(default = false)]
+  pub translate: bool,
+  // 專有名詞或課程術語，作為initial prompt提供給Whisper
+  #[field(validate = validate_initial_prompt())]
+  pub initial_prompt: Option<String>,
+}
+
+fn validate_language<'a>(value: &Option<String>) -> form::Result<'a, ()> {
+  match value {
+    Some(language)
+      if !(2..=3).contains(&language.len())
+        || !language.chars().all(|c| c.is_ascii_lowercase()) =>
+    {
+      log::warn!("Invalid language code: {}", language);
+      Err(Error::validation("Language must be a 2 or 3 letter Whisper language code").into())
+    }
+    _ => Ok(()),
+  }
+}
+
+fn validate_initial_prompt<'a>(value: &Option<String>) -> form::Result<'a, ()> {
+  match value {
+    Some(prompt) if prompt.chars().count() > 1000 => {
+      log::warn!("The initial prompt is too long");
+      Err(Error::validation("The initial prompt must be at most 1000 characters").into())
+    }
+    _ => Ok(()),
+  }
+}
@@ -0,0 +1,47 @@
+use rocket::{
+  form::{self, Error},
+  FromForm,
+};
+use serde::Serialize;
+
+#[derive(FromForm, Debug)]
+pub struct Request {
+  #[field(validate = validate_language())]
+  pub target: String,
+  // 未設定時由翻譯服務自動判斷
+  #[field(validate = validate_optional_language())]
+  pub source: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Track {
+  pub language: String,
+  pub created_at: String,
+}
+
+// BCP 47語言標籤，如 en、zh-TW
+pub fn is_language_tag(tag: &str) -> bool {
+  let mut parts = tag.split('-');
+  let primary = parts.next().unwrap_or_default();
+  (2..=3).contains(&primary.len())
+    && primary.chars().all(|c| c.is_ascii_lowercase())
+    && parts
+      .all(|part| (2..=8).contains(&part.len()) && part.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+fn validate_language<'a>(language: &str) -> form::Result<'a, ()> {
+  match is_language_tag(language) {
+    true => Ok(()),
+    false => {
+      log::warn!("Invalid language tag: {}", language);
+      Err(Error::validation("Invalid language tag").into())
+    }
+  }
+}
+
+fn validate_optional_language<'a>(language: &Option<String>) -> form::Result<'a, ()> {
+  match language {
+    Some(language) => validate_language(language),
+    None => Ok(()),
+  }
+}
@@ -1,3 +1,14 @@
+use crate::model::{
+  audio::AudioOptions,
+  avatar::*,
+  chapter::ChapterOptions,
+  constant::*,
+  generation::{EncodingOptions, GenerationOptions, GenerationRequest, Preset},
+  script::validate_script,
+  slideshow::{validate_durations, validate_timestamps},
+  subtitle::FormatOptions,
+  transcription::TranscriptionOptions,
+};
 use rocket::{
   form::{self, Error},
   fs::TempFile,
@@ -7,23 +18,89 @@ use rocket::{
 
 #[derive(FromForm, Debug)]
 pub struct Request<'a> {
+  // 螢幕錄影，與slides、pdf擇一
   #[field(validate = validate_video())]
-  pub video: TempFile<'a>,
+  pub video: Option<TempFile<'a>>,
+  #[field(validate = validate_slides())]
+  pub slides: Vec<TempFile<'a>>,
+  #[field(validate = validate_pdf())]
+  pub pdf: Option<TempFile<'a>>,
+  // 每張投影片的秒數，或每張投影片在旁白中的開始時間
+  #[field(validate = validate_durations())]
+  pub slide_durations: Vec<f32>,
+  #[field(validate = validate_timestamps())]
+  pub slide_timestamps: Vec<f32>,
   #[field(validate = validate_avatar())]
   pub avatar: TempFile<'a>,
-  #[field(validate = validate_range_0_to_1())]
-  pub x: f32,
-  #[field(validate = validate_range_0_to_1())]
-  pub y: f32,
-  pub shape: String,
+  // 另外錄製的旁白，取代影片中的音訊
+  #[field(validate = validate_audio())]
+  pub audio: Option<TempFile<'a>>,
+  // 以旁白稿透過TTS產生旁白
+  #[field(validate = validate_script())]
+  pub script: Option<String>,
+  // 頭像位置與外觀，欄位為layout.x、layout.shape等
+  pub layout: Layout,
+  #[field(validate = validate_timeline())]
+  pub timeline: Vec<Keyframe>,
   #[field(default = true)]
   pub remove_bg: bool,
+  #[field(default = BackgroundMode::Transparent)]
+  pub background: BackgroundMode,
+  #[field(default = "#00FF00", validate = validate_color())]
+  pub background_color: String,
+  #[field(validate = validate_background_image())]
+  pub background_image: Option<TempFile<'a>>,
   #[field(default = true)]
   pub subtitle: bool,
+  pub transcription: TranscriptionOptions,
+  pub subtitle_format: FormatOptions,
+  // 燒錄字幕時依逐字時間標示目前的字
+  #[field(default = false)]
+  pub karaoke: bool,
+  // 依投影片切換自動產生章節
+  pub chapters: ChapterOptions,
+  pub generation: GenerationRequest,
+  // 音訊後處理，處理後的音訊用於字幕與最終合成
+  pub audio_processing: AudioOptions,
+  // 設定preset時以preset的參數補上generation中未指定的欄位
+  pub preset: Option<Preset>,
+  // 預覽模式只處理前preview_seconds秒
+  #[field(default = false)]
+  pub preview: bool,
+  #[field(default = 10, validate = range(1..=60))]
+  pub preview_seconds: u32,
+  #[field(default = false)]
+  pub keep_files: bool,
 }
 
-fn validate_video<'a>(value: &TempFile<'a>) -> form::Result<'a, ()> {
-  if let Some(content_type) = value.content_type() {
+impl Request<'_> {
+  pub fn generation_options(&self) -> GenerationOptions {
+    self.generation.resolve(self.preset)
+  }
+
+  pub fn encoding_options(&self) -> EncodingOptions {
+    self.preset.unwrap_or(Preset::Standard).encoding()
+  }
+
+  pub fn background_settings(&self) -> Background {
+    Background {
+      mode: self.background,
+      color: self.background_color.clone(),
+      image: self
+        .background_image
+        .as_ref()
+        .map(|image| background_file(image.content_type()).to_string()),
+    }
+  }
+}
+
+fn validate_video<'a>(value: &Option<TempFile<'a>>) -> form::Result<'a, ()> {
+  let video = match value {
+    Some(video) => video,
+    None => return Ok(()),
+  };
+
+  if let Some(content_type) = video.content_type() {
     if content_type == &ContentType::MP4 || content_type == &ContentType::MOV {
       return Ok(());
     }
@@ -42,11 +119,58 @@ fn validate_avatar<'a>(value: &TempFile<'a>) -> form::Result<'a, ()> {
   Err(Error::validation("Invalid file type: PNG or JPEG required").into())
 }
 
-fn validate_range_0_to_1<'a>(value: &f32) -> form::Result<'a, ()> {
-  if *value >= 0.0 && *value <= 1.0 {
-    Ok(())
-  } else {
-    log::warn!("The value must be between 0 and 1");
-    Err(Error::validation("The value must be between 0 and 1").into())
+// 依旁白的格式保留副檔名，不支援的格式回傳None
+pub fn narration_file(content_type: Option<&ContentType>) -> Option<&'static str> {
+  let content_type = content_type?;
+  if content_type.top() != "audio" {
+    return None;
+  }
+  match content_type.sub().as_str() {
+    "wav" | "x-wav" | "wave" => Some(NARRATION_WAV_FILE),
+    "mpeg" => Some(NARRATION_MP3_FILE),
+    "mp4" | "x-m4a" => Some(NARRATION_M4A_FILE),
+    _ => None,
+  }
+}
+
+pub fn validate_audio<'a>(value: &Option<TempFile<'a>>) -> form::Result<'a, ()> {
+  let audio = match value {
+    Some(audio) => audio,
+    None => return Ok(()),
+  };
+
+  if narration_file(audio.content_type()).is_some() {
+    return Ok(());
+  }
+  log::warn!("Invalid file type: WAV, MP3 or M4A required");
+  Err(Error::validation("Invalid file type: WAV, MP3 or M4A required").into())
+}
+
+fn validate_slides<'a>(value: &[TempFile<'a>]) -> form::Result<'a, ()> {
+  for slide in value {
+    validate_avatar(slide)?;
+  }
+  Ok(())
+}
+
+fn validate_pdf<'a>(value: &Option<TempFile<'a>>) -> form::Result<'a, ()> {
+  let pdf = match value {
+    Some(pdf) => pdf,
+    None => return Ok(()),
+  };
+
+  if let Some(content_type) = pdf.content_type() {
+    if content_type == &ContentType::PDF {
+      return Ok(());
+    }
+  }
+  log::warn!("Invalid file type: PDF required");
+  Err(Error::validation("Invalid file type: PDF required").into())
+}
+
+fn validate_background_image<'a>(value: &Option<TempFile<'a>>) -> form::Result<'a, ()> {
+  match value {
+    Some(image) => validate_avatar(image),
+    None => Ok(()),
   }
 }
@@ -1,11 +1,29 @@
-#[derive(Debug)]
+use crate::model::{
+  audio::AudioOptions,
+  avatar::{Background, Layout},
+  chapter::ChapterOptions,
+  script::ScriptSegment,
+  slideshow::Slideshow,
+  subtitle::FormatOptions,
+  transcription::TranscriptionOptions,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GenVideoRequest {
   pub code: String,
-  pub x: f32,
-  pub y: f32,
-  pub shape: String,
+  pub layout: Layout,
+  pub narration: bool,
+  pub script: Option<Vec<ScriptSegment>>,
+  pub slideshow: Option<Slideshow>,
   pub remove_bg: bool,
+  pub background: Background,
+  pub audio: AudioOptions,
+  pub preview: Option<u32>,
   pub subtitle: bool,
+  pub transcription: TranscriptionOptions,
+  pub subtitle_format: FormatOptions,
+  pub chapters: ChapterOptions,
 }
 
 #[derive(Debug)]
@@ -13,7 +31,14 @@ pub struct MergeSubsRequest {
   pub code: String,
 }
 
+#[derive(Debug)]
+pub struct RerenderRequest {
+  pub code: String,
+  pub layout: Layout,
+}
+
 pub struct Sender {
   pub gen_sender: tokio::sync::mpsc::Sender<GenVideoRequest>,
   pub merge_sender: tokio::sync::mpsc::Sender<MergeSubsRequest>,
+  pub rerender_sender: tokio::sync::mpsc::Sender<RerenderRequest>,
 }
@@ -0,0 +1,253 @@
+use crate::utils::*;
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use rocket::{
+  http::{ContentType, Status},
+  request::Request,
+  response::{self, Responder, Response},
+};
+use std::{
+  fs::File,
+  io::{Cursor, Read, Seek, SeekFrom},
+  path::Path,
+  time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::io::AsyncReadExt;
+
+// 超過此數量的區段直接回傳完整檔案
+static MAX_RANGES: usize = 16;
+// 多區段回應需整段讀入記憶體，總長度超過時改回傳完整檔案
+static MAX_MULTIPART_BYTES: u64 = 8 * 1024 * 1024;
+
+#[derive(Debug, PartialEq)]
+pub enum RangeRequest {
+  Full,
+  Partial(Vec<(u64, u64)>),
+  Unsatisfiable,
+}
+
+#[derive(Debug)]
+pub struct RangedFile {
+  file: File,
+  len: u64,
+  modified: SystemTime,
+  content_type: ContentType,
+}
+
+impl RangedFile {
+  pub async fn open<P: AsRef<Path>>(path: P) -> Result<RangedFile, Error> {
+    let path = path.as_ref();
+    log::debug!("Opening ranged file '{}'", path.display());
+
+    let file = handle(
+      File::open(path),
+      &format!("Opening file '{}'", path.display()),
+    )?;
+    let metadata = handle(
+      file.metadata(),
+      &format!("Reading metadata of '{}'", path.display()),
+    )?;
+    let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+    let content_type = path
+      .extension()
+      .and_then(|ext| ext.to_str())
+      .and_then(ContentType::from_extension)
+      .unwrap_or(ContentType::Binary);
+
+    Ok(RangedFile {
+      file,
+      len: metadata.len(),
+      modified,
+      content_type,
+    })
+  }
+
+  fn etag(&self) -> String {
+    let mtime = self
+      .modified
+      .duration_since(UNIX_EPOCH)
+      .map(|d| d.as_secs())
+      .unwrap_or(0);
+    format!("\"{:x}-{:x}\"", self.len, mtime)
+  }
+
+  fn last_modified(&self) -> String {
+    DateTime::<Utc>::from(self.modified)
+      .format("%a, %d %b %Y %H:%M:%S GMT")
+      .to_string()
+  }
+
+  // If-Range不符時忽略Range，回傳完整檔案
+  pub fn requested_range(&self, range: Option<&str>, if_range: Option<&str>) -> RangeRequest {
+    match (range, if_range) {
+      (Some(range), Some(if_range))
+        if if_range == self.etag() || if_range == self.last_modified() =>
+      {
+        parse_range(range, self.len)
+      }
+      (Some(_), Some(_)) => RangeRequest::Full,
+      (Some(range), None) => parse_range(range, self.len),
+      (None, _) => RangeRequest::Full,
+    }
+  }
+
+  // 續傳請求：實際回應的區段不從檔案開頭開始
+  pub fn is_continuation(&self, range: Option<&str>, if_range: Option<&str>) -> bool {
+    match self.requested_range(range, if_range) {
+      RangeRequest::Partial(ranges) => ranges.first().is_some_and(|(start, _)| *start > 0),
+      _ => false,
+    }
+  }
+
+  fn read_range(&mut self, start: u64, end: u64) -> Result<Vec<u8>, Error> {
+    let mut buf = vec![0; (end - start + 1) as usize];
+    handle(self.file.seek(SeekFrom::Start(start)), "Seeking file")?;
+    handle(self.file.read_exact(&mut buf), "Reading file")?;
+    Ok(buf)
+  }
+}
+
+impl<'r> Responder<'r, 'static> for RangedFile {
+  fn respond_to(mut self, req: &'r Request<'_>) -> response::Result<'static> {
+    let etag = self.etag();
+    let last_modified = self.last_modified();
+
+    let mut builder = Response::build();
+    builder
+      .raw_header("Accept-Ranges", "bytes")
+      .raw_header("ETag", etag.clone())
+      .raw_header("Last-Modified", last_modified.clone());
+
+    let range = self.requested_range(
+      req.headers().get_one("Range"),
+      req.headers().get_one("If-Range"),
+    );
+    log::debug!("range={:?}", range);
+
+    match range {
+      RangeRequest::Full => {
+        builder
+          .header(self.content_type)
+          .sized_body(None, tokio::fs::File::from_std(self.file));
+      }
+      RangeRequest::Unsatisfiable => {
+        builder
+          .status(Status::RangeNotSatisfiable)
+          .raw_header("Content-Range", format!("bytes */{}", self.len));
+      }
+      RangeRequest::Partial(ranges) if ranges.len() == 1 => {
+        let (start, end) = ranges[0];
+        self
+          .file
+          .seek(SeekFrom::Start(start))
+          .map_err(|_| Status::InternalServerError)?;
+        let body = tokio::fs::File::from_std(self.file).take(end - start + 1);
+
+        builder
+          .status(Status::PartialContent)
+          .header(self.content_type)
+          .raw_header(
+            "Content-Range",
+            format!("bytes {}-{}/{}", start, end, self.len),
+          )
+          .raw_header("Content-Length", (end - start + 1).to_string())
+          .streamed_body(body);
+      }
+      RangeRequest::Partial(ranges) => {
+        let boundary = format!("{:016x}", rand::thread_rng().gen::<u64>());
+        let mut body = Vec::new();
+
+        for (start, end) in ranges {
+          body.extend_from_slice(
+            format!(
+              "\r\n--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+              boundary, self.content_type, start, end, self.len
+            )
+            .as_bytes(),
+          );
+          let data = self
+            .read_range(start, end)
+            .map_err(|_| Status::InternalServerError)?;
+          body.extend_from_slice(&data);
+        }
+        body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+        builder
+          .status(Status::PartialContent)
+          .raw_header(
+            "Content-Type",
+            format!("multipart/byteranges; boundary={}", boundary),
+          )
+          .sized_body(body.len(), Cursor::new(body));
+      }
+    }
+
+    builder.ok()
+  }
+}
+
+pub fn parse_range(header: &str, len: u64) -> RangeRequest {
+  let specs = match header.trim().strip_prefix("bytes=") {
+    Some(specs) => specs,
+    None => return RangeRequest::Full,
+  };
+
+  let mut ranges = Vec::new();
+  for spec in specs.split(',') {
+    let (start, end) = match spec.trim().split_once('-') {
+      Some(pair) => pair,
+      None => return RangeRequest::Full,
+    };
+
+    let range = match (start.trim(), end.trim()) {
+      ("", "") => return RangeRequest::Full,
+      // bytes=-N：最後N個位元組
+      ("", suffix) => match suffix.parse::<u64>() {
+        Ok(0) => None,
+        Ok(n) if len > 0 => Some((len.saturating_sub(n), len - 1)),
+        Ok(_) => None,
+        Err(_) => return RangeRequest::Full,
+      },
+      (start, "") => match start.parse::<u64>() {
+        Ok(start) if start < len => Some((start, len - 1)),
+        Ok(_) => None,
+        Err(_) => return RangeRequest::Full,
+      },
+      (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+        (Ok(start), Ok(end)) if start > end => return RangeRequest::Full,
+        (Ok(start), Ok(end)) if start < len => Some((start, end.min(len - 1))),
+        (Ok(_), Ok(_)) => None,
+        (_, _) => return RangeRequest::Full,
+      },
+    };
+
+    if let Some(range) = range {
+      ranges.push(range);
+    }
+  }
+
+  if ranges.is_empty() {
+    return RangeRequest::Unsatisfiable;
+  }
+  if ranges.len() > MAX_RANGES {
+    return RangeRequest::Full;
+  }
+
+  // 合併重疊或相鄰的區段
+  ranges.sort();
+  let mut merged: Vec<(u64, u64)> = Vec::new();
+  for (start, end) in ranges {
+    match merged.last_mut() {
+      Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+      _ => merged.push((start, end)),
+    }
+  }
+  if merged.len() > 1 {
+    let total: u64 = merged.iter().map(|(start, end)| end - start + 1).sum();
+    if total > MAX_MULTIPART_BYTES {
+      return RangeRequest::Full;
+    }
+  }
+
+  RangeRequest::Partial(merged)
+}
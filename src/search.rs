@@ -0,0 +1,60 @@
+use crate::formatter::is_cjk;
+
+// snippet標記，輸出前換成<mark>
+pub static MARK_START: char = '\u{E000}';
+pub static MARK_END: char = '\u{E001}';
+
+// unicode61斷詞不會切開連續的CJK字，索引與查詢時在每個CJK字間加上空白
+pub fn fts_text(text: &str) -> String {
+  let mut spaced = String::new();
+  for c in text.chars() {
+    if is_cjk(c) {
+      spaced.push(' ');
+      spaced.push(c);
+      spaced.push(' ');
+    } else {
+      spaced.push(c);
+    }
+  }
+  spaced.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+// 每個詞作為一個片語，全部需符合
+pub fn fts_query(q: &str) -> Option<String> {
+  let terms: Vec<String> = q
+    .split_whitespace()
+    .map(|term| format!("\"{}\"", fts_text(term).replace('"', "\"\"")))
+    .collect();
+  match terms.is_empty() {
+    true => None,
+    false => Some(terms.join(" AND ")),
+  }
+}
+
+// 移除fts_text加入的空白並換上標記，字幕內容先做HTML跳脫
+pub fn clean_snippet(snippet: &str) -> String {
+  let chars: Vec<char> = snippet.chars().collect();
+  let is_mark = |c: &char| *c == MARK_START || *c == MARK_END;
+
+  let mut cleaned = String::new();
+  for (i, c) in chars.iter().enumerate() {
+    if *c == ' ' {
+      let before = chars[..i].iter().rev().find(|c| !is_mark(c));
+      let after = chars[i + 1..].iter().find(|c| !is_mark(c));
+      if before.is_some_and(|c| is_cjk(*c)) && after.is_some_and(|c| is_cjk(*c)) {
+        continue;
+      }
+    }
+    match *c {
+      c if c == MARK_START => cleaned.push_str("<mark>"),
+      c if c == MARK_END => cleaned.push_str("</mark>"),
+      '&' => cleaned.push_str("&amp;"),
+      '<' => cleaned.push_str("&lt;"),
+      '>' => cleaned.push_str("&gt;"),
+      '"' => cleaned.push_str("&quot;"),
+      '\'' => cleaned.push_str("&#39;"),
+      c => cleaned.push(c),
+    }
+  }
+  cleaned.replace("</mark><mark>", "")
+}
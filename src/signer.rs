@@ -0,0 +1,174 @@
+use crate::{database, model::constant::*, utils::*};
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::Sha256;
+use std::env;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug)]
+pub struct DownloadLink {
+  pub code: String,
+  pub resource: String,
+  pub expires: i64,
+  pub nonce: Option<String>,
+  pub signature: String,
+}
+
+impl DownloadLink {
+  pub fn query(&self) -> String {
+    let mut query = format!("expires={}&signature={}", self.expires, self.signature);
+    if let Some(nonce) = &self.nonce {
+      query.push_str(&format!("&nonce={}", nonce));
+    }
+    query
+  }
+
+  pub fn to_url(&self) -> String {
+    let base = env::var("BASE_URL").unwrap_or_else(|_| DEFAULT_BASE_URL.to_string());
+    let path = if self.resource == DOWNLOAD_RESOURCE {
+      format!("/download/{}", self.code)
+    } else if self.resource == BUNDLE_RESOURCE {
+      format!("/api/tasks/{}/bundle.zip", self.code)
+    } else {
+      format!("/api/tasks/{}/artifacts/{}", self.code, self.resource)
+    };
+    format!("{}{}?{}", base, path, self.query())
+  }
+}
+
+// resource為download、bundle或artifact名稱，連結只能用於該資源
+pub fn sign_download(
+  code: &str,
+  resource: &str,
+  ttl: i64,
+  single_use: bool,
+) -> Result<DownloadLink, Error> {
+  log::info!("Signing {} link for code: {}", resource, code);
+
+  let expires = get_timestamp() + ttl;
+  let nonce = match single_use {
+    true => {
+      let nonce = generate_nonce();
+      handle(
+        database::insert_download_token(code, &nonce),
+        &format!("Inserting download token for code: {}", code),
+      )?;
+      Some(nonce)
+    }
+    false => None,
+  };
+
+  let signature = compute_signature(code, resource, expires, nonce.as_deref());
+  log::debug!("expires={}, nonce={:?}", expires, nonce);
+
+  Ok(DownloadLink {
+    code: code.to_string(),
+    resource: resource.to_string(),
+    expires,
+    nonce,
+    signature,
+  })
+}
+
+pub fn verify_download(
+  code: &str,
+  resource: &str,
+  expires: i64,
+  nonce: Option<&str>,
+  signature: &str,
+) -> Result<(), Error> {
+  log::info!("Verifying download link for code: {}", code);
+
+  let signature = match decode_hex(signature) {
+    Some(bytes) => bytes,
+    None => {
+      log::warn!("Malformed signature for code: {}", code);
+      return Err(Error::new(
+        ErrorKind::PermissionDenied,
+        "Malformed signature",
+      ));
+    }
+  };
+
+  let mut mac = new_mac();
+  mac.update(payload(code, resource, expires, nonce).as_bytes());
+  if mac.verify_slice(&signature).is_err() {
+    log::warn!("Invalid signature for code: {}", code);
+    return Err(Error::new(ErrorKind::PermissionDenied, "Invalid signature"));
+  }
+
+  if expires < get_timestamp() {
+    log::warn!("Download link expired for code: {}", code);
+    return Err(Error::new(ErrorKind::PermissionDenied, "Link expired"));
+  }
+
+  Ok(())
+}
+
+// 第一次請求時標記為已使用，之後只在短時間內接受同一個IP不從開頭開始的Range請求（播放器拖曳、續傳）
+pub fn consume_nonce(
+  code: &str,
+  nonce: &str,
+  client_ip: Option<&str>,
+  continuation: bool,
+) -> Result<(), Error> {
+  log::info!("Consuming download token for code: {}", code);
+
+  let consumed = handle(
+    database::consume_download_token(code, nonce, client_ip, continuation),
+    &format!("Consuming download token for code: {}", code),
+  )?;
+
+  if consumed {
+    Ok(())
+  } else {
+    log::warn!("Download token already used for code: {}", code);
+    Err(Error::new(ErrorKind::PermissionDenied, "Link already used"))
+  }
+}
+
+fn compute_signature(code: &str, resource: &str, expires: i64, nonce: Option<&str>) -> String {
+  let mut mac = new_mac();
+  mac.update(payload(code, resource, expires, nonce).as_bytes());
+  encode_hex(&mac.finalize().into_bytes())
+}
+
+// 啟動時確認已設定簽章金鑰，避免請求時才失敗
+pub fn check_secret() -> Result<(), Error> {
+  match env::var("DOWNLOAD_SECRET") {
+    Ok(secret) if !secret.is_empty() => Ok(()),
+    _ => Err(Error::new(
+      ErrorKind::NotFound,
+      "DOWNLOAD_SECRET is not set",
+    )),
+  }
+}
+
+fn new_mac() -> HmacSha256 {
+  let secret = env::var("DOWNLOAD_SECRET").expect("Failed to get download secret");
+  HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size")
+}
+
+fn payload(code: &str, resource: &str, expires: i64, nonce: Option<&str>) -> String {
+  format!("{}|{}|{}|{}", code, resource, expires, nonce.unwrap_or(""))
+}
+
+fn generate_nonce() -> String {
+  let bytes: [u8; 16] = rand::thread_rng().gen();
+  encode_hex(&bytes)
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+  s.as_bytes()
+    .chunks(2)
+    .map(|pair| match pair.len() {
+      2 => u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok(),
+      _ => None,
+    })
+    .collect()
+}
@@ -0,0 +1,595 @@
+use crate::{
+  database, fonts,
+  formatter::is_cjk,
+  model::constant::*,
+  model::revision::{Change, Operation, ORIGINAL_AUTHOR},
+  model::subtitle::{Subtitle, Word},
+  model::transcript,
+  utils::*,
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+// Whisper輸出的逐字時間，單位為秒
+#[derive(Serialize, Deserialize)]
+struct WhisperWord {
+  word: String,
+  start: f64,
+  end: f64,
+}
+
+pub fn load_subtitles(code: &str) -> Result<Option<Vec<Subtitle>>, Error> {
+  log::info!("Loading subtitles for code: {}", code);
+
+  // 優先使用使用者編輯過的字幕，其次才是Whisper產生的srt
+  let subtitles = handle(
+    database::get_subtitles(code),
+    &format!("Getting subtitles for code: {}", code),
+  )?;
+  if !subtitles.is_empty() {
+    return Ok(Some(subtitles));
+  }
+
+  match get_file_path(code, SUBS_FILE) {
+    Ok(path) => {
+      let content = handle(
+        fs::read_to_string(&path),
+        &format!("Reading file '{}'", path),
+      )?;
+      let mut subtitles = parse_srt(&content);
+      attach_words(code, &mut subtitles)?;
+      Ok(Some(subtitles))
+    }
+    Err(_) => Ok(None),
+  }
+}
+
+// words.json為每句字幕對應的逐字時間，句數不符時忽略
+// 保留字前的空白，CJK的字之間沒有空白
+fn attach_words(code: &str, subtitles: &mut [Subtitle]) -> Result<(), Error> {
+  let path = match get_file_path(code, WORDS_FILE) {
+    Ok(path) => path,
+    Err(_) => return Ok(()),
+  };
+  let content = handle(
+    fs::read_to_string(&path),
+    &format!("Reading file '{}'", path),
+  )?;
+  let words = match parse_words(&content) {
+    Some(words) if words.len() == subtitles.len() => words,
+    _ => {
+      log::warn!("Ignoring word timestamps for code: {}", code);
+      return Ok(());
+    }
+  };
+
+  for (subtitle, words) in subtitles.iter_mut().zip(words) {
+    subtitle.words = words;
+  }
+  Ok(())
+}
+
+pub fn parse_words(content: &str) -> Option<Vec<Vec<Word>>> {
+  let cues: Vec<Vec<WhisperWord>> = serde_json::from_str(content).ok()?;
+  Some(
+    cues
+      .into_iter()
+      .map(|words| {
+        words
+          .into_iter()
+          .filter(|word| !word.word.trim().is_empty())
+          .map(|word| Word {
+            text: word.word.trim_end().to_string(),
+            start_time: format_time((word.start.max(0.0) * 1000.0).round() as u64, ','),
+            end_time: format_time((word.end.max(0.0) * 1000.0).round() as u64, ','),
+          })
+          .collect()
+      })
+      .collect(),
+  )
+}
+
+pub fn parse_srt(content: &str) -> Vec<Subtitle> {
+  let content = content.replace("\r\n", "\n");
+  let mut subtitles = Vec::new();
+
+  for block in content.split("\n\n") {
+    let mut lines = block.lines().skip_while(|line| line.trim().is_empty());
+    let mut timing = match lines.next() {
+      Some(line) => line,
+      None => continue,
+    };
+    // 編號行可省略
+    if !timing.contains("-->") {
+      timing = match lines.next() {
+        Some(line) => line,
+        None => continue,
+      };
+    }
+
+    let (start_time, end_time) = match timing.split_once("-->") {
+      Some((start, end)) => (start.trim(), end.trim()),
+      None => continue,
+    };
+    if parse_time(start_time).is_none() || parse_time(end_time).is_none() {
+      log::warn!("Skipping cue with invalid timing: {}", timing);
+      continue;
+    }
+
+    let text = lines.collect::<Vec<&str>>().join("\n");
+    subtitles.push(Subtitle::new(text.trim(), start_time, end_time));
+  }
+
+  subtitles
+}
+
+// parse_words的反向，重新排版後寫回words.json
+pub fn to_words(subtitles: &[Subtitle]) -> Result<String, Error> {
+  let cues: Vec<Vec<WhisperWord>> = subtitles
+    .iter()
+    .map(|sub| {
+      sub
+        .words
+        .iter()
+        .map(|word| WhisperWord {
+          word: word.text.clone(),
+          start: parse_time(&word.start_time).unwrap_or(0) as f64 / 1000.0,
+          end: parse_time(&word.end_time).unwrap_or(0) as f64 / 1000.0,
+        })
+        .collect()
+    })
+    .collect();
+  Ok(serde_json::to_string(&cues)?)
+}
+
+pub fn to_srt(subtitles: &[Subtitle]) -> String {
+  subtitles
+    .iter()
+    .enumerate()
+    .map(|(i, sub)| {
+      format!(
+        "{}\n{} --> {}\n{}\n\n",
+        i + 1,
+        format_time(parse_time(&sub.start_time).unwrap_or(0), ','),
+        format_time(parse_time(&sub.end_time).unwrap_or(0), ','),
+        sub.text
+      )
+    })
+    .collect()
+}
+
+pub fn to_vtt(subtitles: &[Subtitle]) -> String {
+  let cues: String = subtitles
+    .iter()
+    .map(|sub| {
+      format!(
+        "{} --> {}\n{}\n\n",
+        format_time(parse_time(&sub.start_time).unwrap_or(0), '.'),
+        format_time(parse_time(&sub.end_time).unwrap_or(0), '.'),
+        vtt_text(sub)
+      )
+    })
+    .collect();
+  format!("WEBVTT\n\n{}", cues)
+}
+
+// 有逐字時間時在每個字前加上時間標記，第一個字從句首開始
+fn vtt_text(sub: &Subtitle) -> String {
+  if sub.words.is_empty() {
+    return sub.text.clone();
+  }
+  sub
+    .words
+    .iter()
+    .enumerate()
+    .map(|(i, word)| match i {
+      0 => word.text.trim_start().to_string(),
+      _ => format!(
+        "<{}>{}",
+        format_time(parse_time(&word.start_time).unwrap_or(0), '.'),
+        word.text
+      ),
+    })
+    .collect()
+}
+
+// karaoke時以\kf依逐字時間標示目前的字，每種字型、字級、顏色與是否逐字標示的組合各一個樣式
+pub fn to_ass(subtitles: &[Subtitle], karaoke: bool) -> String {
+  let mut styles: Vec<(&str, u32, &str, bool)> = Vec::new();
+  for sub in subtitles {
+    let style = ass_style(sub, karaoke);
+    if !styles.contains(&style) {
+      styles.push(style);
+    }
+  }
+  if styles.is_empty() {
+    styles.push((DEFAULT_FONT, 32, "white", false));
+  }
+
+  let mut ass = String::from(
+    "[Script Info]\nScriptType: v4.00+\nWrapStyle: 0\nScaledBorderAndShadow: yes\n\n\
+     [V4+ Styles]\n\
+     Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding\n",
+  );
+  for (i, (font, fontsize, color, karaoke)) in styles.iter().enumerate() {
+    // 樣式以逗號分隔，family名稱不能含逗號
+    let family = fonts::font_family(font)
+      .unwrap_or_else(|| DEFAULT_FONT_FAMILY.to_string())
+      .replace(',', " ");
+    let color = ass_color(color);
+    // 有逐字時間時已唱過的字以黃色標示，未唱到的字使用字幕顏色
+    let primary = match karaoke {
+      true => "&H0000FFFF",
+      false => color.as_str(),
+    };
+    ass.push_str(&format!(
+      "Style: {},{},{},{},{},&H00000000,&H00000000,0,0,0,0,100,100,0,0,1,2,0,2,10,10,20,1\n",
+      style_name(i),
+      family,
+      fontsize,
+      primary,
+      color
+    ));
+  }
+  ass.push_str(
+    "\n[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n",
+  );
+
+  for sub in subtitles {
+    let start = parse_time(&sub.start_time).unwrap_or(0);
+    let end = parse_time(&sub.end_time).unwrap_or(0);
+    let style = styles
+      .iter()
+      .position(|style| *style == ass_style(sub, karaoke))
+      .unwrap_or(0);
+    let text = match ass_style(sub, karaoke).3 {
+      true => karaoke_text(start, &sub.words),
+      false => escape_ass(&sub.text).replace('\n', "\\N"),
+    };
+    ass.push_str(&format!(
+      "Dialogue: 0,{},{},{},,0,0,0,,{}\n",
+      format_ass_time(start),
+      format_ass_time(end),
+      style_name(style),
+      text
+    ));
+  }
+  ass
+}
+
+fn ass_style(sub: &Subtitle, karaoke: bool) -> (&str, u32, &str, bool) {
+  (
+    sub.font.as_str(),
+    sub.fontsize,
+    sub.color.as_str(),
+    karaoke && !sub.words.is_empty(),
+  )
+}
+
+fn style_name(index: usize) -> String {
+  match index {
+    0 => String::from("Default"),
+    _ => format!("Style{}", index),
+  }
+}
+
+// 字幕內容中的大括號會被當成樣式覆寫標籤
+fn escape_ass(text: &str) -> String {
+  text.replace('{', "\\{").replace('}', "\\}")
+}
+
+// ASS顏色為&HAABBGGRR，支援#RRGGBB與常用顏色名稱，其餘視為白色
+fn ass_color(color: &str) -> String {
+  let rgb = match color.trim().to_ascii_lowercase().as_str() {
+    hex if hex.len() == 7 && hex.starts_with('#') => {
+      u32::from_str_radix(&hex[1..], 16).unwrap_or(0xFFFFFF)
+    }
+    "black" => 0x000000,
+    "red" => 0xFF0000,
+    "green" => 0x00FF00,
+    "blue" => 0x0000FF,
+    "yellow" => 0xFFFF00,
+    "cyan" => 0x00FFFF,
+    "magenta" => 0xFF00FF,
+    "gray" | "grey" => 0x808080,
+    _ => 0xFFFFFF,
+  };
+  format!(
+    "&H00{:02X}{:02X}{:02X}",
+    rgb & 0xFF,
+    rgb >> 8 & 0xFF,
+    rgb >> 16 & 0xFF
+  )
+}
+
+fn karaoke_text(start: u64, words: &[Word]) -> String {
+  let mut cursor = start;
+  let mut text = String::new();
+
+  for (i, word) in words.iter().enumerate() {
+    let word_text = match i {
+      0 => word.text.trim_start(),
+      _ => word.text.as_str(),
+    };
+    let word_start = parse_time(&word.start_time).unwrap_or(cursor).max(cursor);
+    let word_end = parse_time(&word.end_time)
+      .unwrap_or(word_start)
+      .max(word_start);
+    // 字與字之間的空檔以\k帶過，不顯示漸進效果
+    if word_start > cursor {
+      text.push_str(&format!("{{\\k{}}}", (word_start - cursor) / 10));
+    }
+    text.push_str(&format!(
+      "{{\\kf{}}}{}",
+      (word_end - word_start) / 10,
+      escape_ass(word_text).replace('\n', "\\N")
+    ));
+    cursor = word_end;
+  }
+  text
+}
+
+// ASS時間格式為 H:MM:SS.cc
+fn format_ass_time(ms: u64) -> String {
+  format!(
+    "{}:{:02}:{:02}.{:02}",
+    ms / 3_600_000,
+    ms / 60_000 % 60,
+    ms / 1000 % 60,
+    ms % 1000 / 10
+  )
+}
+
+// 依停頓長度將字幕合併為段落，回傳段落開始時間與內容
+pub fn to_paragraphs(subtitles: &[Subtitle], pause: u64) -> Vec<(u64, String)> {
+  let mut paragraphs: Vec<(u64, String)> = Vec::new();
+  let mut last_end = None;
+
+  for sub in subtitles {
+    let start = parse_time(&sub.start_time).unwrap_or(0);
+    let text = sub.text.replace('\n', " ");
+    let text = text.trim();
+    if text.is_empty() {
+      continue;
+    }
+
+    match (paragraphs.last_mut(), last_end) {
+      (Some((_, paragraph)), Some(end)) if start.saturating_sub(end) < pause => {
+        // CJK之間不加空白
+        let cjk =
+          paragraph.chars().last().is_some_and(is_cjk) && text.chars().next().is_some_and(is_cjk);
+        if !cjk {
+          paragraph.push(' ');
+        }
+        paragraph.push_str(text);
+      }
+      _ => paragraphs.push((start, text.to_string())),
+    }
+    last_end = Some(parse_time(&sub.end_time).unwrap_or(start));
+  }
+  paragraphs
+}
+
+pub fn to_transcript_document(subtitles: &[Subtitle], query: &transcript::Query) -> String {
+  let paragraphs = to_paragraphs(subtitles, query.pause);
+  let heading = |start: u64| {
+    let time = format_time(start, ',');
+    let time = time.split(',').next().unwrap_or_default().to_string();
+    match query.format {
+      transcript::Format::Md => format!("## {}\n\n", time),
+      transcript::Format::Txt => format!("[{}]\n", time),
+    }
+  };
+
+  let body = paragraphs
+    .iter()
+    .map(|(start, text)| match query.timestamps {
+      true => format!("{}{}\n", heading(*start), text),
+      false => format!("{}\n", text),
+    })
+    .collect::<Vec<String>>()
+    .join("\n");
+
+  match query.format {
+    transcript::Format::Md => format!("# Transcript\n\n{}", body),
+    transcript::Format::Txt => body,
+  }
+}
+
+// "HH:MM:SS,mmm" 或 "HH:MM:SS.mmm" 轉為毫秒
+pub fn parse_time(time: &str) -> Option<u64> {
+  let time = time.trim().replace(',', ".");
+  let (hms, millis) = match time.split_once('.') {
+    Some((hms, millis)) => (hms.to_string(), millis.to_string()),
+    None => (time, String::from("0")),
+  };
+
+  let parts: Vec<u64> = hms
+    .split(':')
+    .map(|part| part.parse::<u64>().ok())
+    .collect::<Option<Vec<u64>>>()?;
+  let (h, m, s) = match parts.as_slice() {
+    [h, m, s] => (*h, *m, *s),
+    [m, s] => (0, *m, *s),
+    _ => return None,
+  };
+  if m >= 60 || s >= 60 || millis.is_empty() || millis.len() > 3 {
+    return None;
+  }
+
+  let millis = format!("{:0<3}", millis).parse::<u64>().ok()?;
+  Some(((h * 60 + m) * 60 + s) * 1000 + millis)
+}
+
+pub fn format_time(ms: u64, separator: char) -> String {
+  format!(
+    "{:02}:{:02}:{:02}{}{:03}",
+    ms / 3_600_000,
+    ms / 60_000 % 60,
+    ms / 1000 % 60,
+    separator,
+    ms % 1000
+  )
+}
+
+// 依對應函式調整字幕與逐字時間，結果小於0時以0計算
+// 因此縮成結束不晚於開始的字幕會被移除
+// 無法解析的時間不當成0處理，整批拒絕
+fn check_times(subtitles: &[Subtitle]) -> Result<(), String> {
+  for (i, sub) in subtitles.iter().enumerate() {
+    let times = [&sub.start_time, &sub.end_time].into_iter().chain(
+      sub
+        .words
+        .iter()
+        .flat_map(|word| [&word.start_time, &word.end_time]),
+    );
+    for time in times {
+      if parse_time(time).is_none() {
+        return Err(format!("Subtitle {} has incorrect time format", i));
+      }
+    }
+  }
+  Ok(())
+}
+
+fn map_times(
+  subtitles: &mut Vec<Subtitle>,
+  selected: impl Fn(&Subtitle) -> bool,
+  f: impl Fn(u64) -> i64,
+) -> Result<(), String> {
+  check_times(subtitles)?;
+  let convert = |time: &str| {
+    let time = parse_time(time).unwrap_or_default();
+    format_time(f(time).max(0) as u64, ',')
+  };
+
+  for sub in subtitles.iter_mut().filter(|sub| selected(sub)) {
+    sub.start_time = convert(&sub.start_time);
+    sub.end_time = convert(&sub.end_time);
+    for word in sub.words.iter_mut() {
+      word.start_time = convert(&word.start_time);
+      word.end_time = convert(&word.end_time);
+    }
+  }
+  subtitles.retain(|sub| parse_time(&sub.end_time) > parse_time(&sub.start_time));
+  Ok(())
+}
+
+pub fn shift_subtitles(
+  subtitles: &mut Vec<Subtitle>,
+  offset: i64,
+  from: Option<u64>,
+  to: Option<u64>,
+) -> Result<(), String> {
+  let in_range = |sub: &Subtitle| {
+    parse_time(&sub.start_time)
+      .is_some_and(|start| from.is_none_or(|from| start >= from) && to.is_none_or(|to| start <= to))
+  };
+  map_times(subtitles, in_range, |time| {
+    (time as i64).saturating_add(offset)
+  })
+}
+
+// 兩個同步點之間線性對應，範圍外依同樣比例延伸
+pub fn scale_subtitles(
+  subtitles: &mut Vec<Subtitle>,
+  (source_start, target_start): (u64, u64),
+  (source_end, target_end): (u64, u64),
+) -> Result<(), String> {
+  if source_end <= source_start || target_end <= target_start {
+    return Err(String::from("Sync points must be in increasing order"));
+  }
+
+  let ratio = (target_end - target_start) as f64 / (source_end - source_start) as f64;
+  map_times(
+    subtitles,
+    |_| true,
+    |time| {
+      (target_start as i64)
+        .saturating_add(((time as f64 - source_start as f64) * ratio).round() as i64)
+    },
+  )
+}
+
+// 尚未編輯過字幕時，以目前的srt作為第0版
+pub fn record_original_revision(code: &str) -> Result<(), Error> {
+  let edited = handle(
+    database::get_subtitles(code),
+    &format!("Getting subtitles for code: {}", code),
+  )?;
+  if !edited.is_empty() {
+    return Ok(());
+  }
+
+  if let Some(subtitles) = load_subtitles(code)? {
+    handle(
+      database::insert_original_revision(code, &subtitles, ORIGINAL_AUTHOR),
+      &format!("Inserting original revision for code: {}", code),
+    )?;
+  }
+  Ok(())
+}
+
+// LCS表的格數上限，超過時不同的段落直接視為全部刪除後新增
+static MAX_DIFF_CELLS: usize = 1_000_000;
+
+// 以最長共同子序列比較兩個版本，相同的開頭與結尾不列入比較
+pub fn diff_subtitles(old: &[Subtitle], new: &[Subtitle]) -> Vec<Change> {
+  let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+  let suffix = old[prefix..]
+    .iter()
+    .rev()
+    .zip(new[prefix..].iter().rev())
+    .take_while(|(a, b)| a == b)
+    .count();
+  let old = &old[prefix..old.len() - suffix];
+  let new = &new[prefix..new.len() - suffix];
+  let removed = |i: usize| Change {
+    op: Operation::Removed,
+    index: prefix + i,
+    cue: old[i].clone(),
+  };
+  let added = |j: usize| Change {
+    op: Operation::Added,
+    index: prefix + j,
+    cue: new[j].clone(),
+  };
+
+  if old.len().saturating_mul(new.len()) > MAX_DIFF_CELLS {
+    log::warn!(
+      "Too many changed subtitles to diff: {} x {}",
+      old.len(),
+      new.len()
+    );
+    return (0..old.len())
+      .map(removed)
+      .chain((0..new.len()).map(added))
+      .collect();
+  }
+
+  let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+  for i in (0..old.len()).rev() {
+    for j in (0..new.len()).rev() {
+      lcs[i][j] = match old[i] == new[j] {
+        true => lcs[i + 1][j + 1] + 1,
+        false => lcs[i + 1][j].max(lcs[i][j + 1]),
+      };
+    }
+  }
+
+  let mut changes = Vec::new();
+  let (mut i, mut j) = (0, 0);
+  while i < old.len() || j < new.len() {
+    if i < old.len() && j < new.len() && old[i] == new[j] {
+      i += 1;
+      j += 1;
+    } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+      changes.push(added(j));
+      j += 1;
+    } else {
+      changes.push(removed(i));
+      i += 1;
+    }
+  }
+  changes
+}
@@ -230,18 +230,20 @@ fn test_set_subtitle() {
     text: "da".to_string(),
     fontsize: 32,
     color: "white".to_string(),
-    font: "./NotoSansCJK-Regular.ttc".to_string(),
+    font: "NotoSansCJK-Regular".to_string(),
     start_time: "00:00:00,000".to_string(),
     end_time: "00:00:00,500".to_string(),
+    words: Vec::new(),
   };
 
   let subtitle2 = subtitle::Subtitle {
     text: "subtitle2".to_string(),
     fontsize: 32,
     color: "white".to_string(),
-    font: "./NotoSansCJK-Regular.ttc".to_string(),
+    font: "NotoSansCJK-Regular".to_string(),
     start_time: "00:00:01,000".to_string(),
     end_time: "00:00:01,500".to_string(),
+    words: Vec::new(),
   };
 
   let mut form_data = HashMap::new();
@@ -272,3 +274,304 @@ fn test_set_subtitle() {
 
   assert_eq!(response.status(), Status::Ok);
 }
+
+#[test]
+fn test_rerender_queue_full() {
+  let code = "rerenderfull";
+  dotenv().ok();
+  crate::database::init_db();
+  let _ = crate::database::delete_task_by_code(code);
+  crate::database::insert_task(code, false, true).expect("Failed to insert task");
+  crate::database::update_task_status(code, task::Status::Finish).unwrap();
+  crate::database::update_video_status(code, task::Status::Finish).unwrap();
+  crate::utils::create_code_dir(code).expect("Failed to create code directory");
+  for filename in [constant::VIDEO_FILE, constant::AVATAR_VIDEO_FILE] {
+    crate::utils::create_file(code, filename).expect("Failed to create file");
+  }
+
+  // 佇列已滿
+  let (gen_sender, _gen_rx) = mpsc::channel(1);
+  let (merge_sender, _merge_rx) = mpsc::channel(1);
+  let (rerender_sender, _rerender_rx) = mpsc::channel(1);
+  rerender_sender
+    .try_send(worker::RerenderRequest {
+      code: code.to_string(),
+      layout: Form::<avatar::Layout>::parse("x=0.5&y=0.5&shape=circle").unwrap(),
+    })
+    .unwrap();
+  let sender = worker::Sender {
+    gen_sender,
+    merge_sender,
+    rerender_sender,
+  };
+
+  let rocket = rocket::build().mount("/", routes![rerender]).manage(sender);
+  let client = Client::untracked(rocket).expect("valid rocket instance");
+  let response = client
+    .post(format!("/api/gen/{}/rerender", code))
+    .header(ContentType::Form)
+    .body("x=0.5&y=0.5&shape=circle")
+    .dispatch();
+  assert_eq!(response.status(), Status::ServiceUnavailable);
+
+  // 狀態已還原，可再次重新合成
+  let task = crate::database::get_task_info(code).unwrap();
+  assert!(matches!(task.status, task::Status::Finish));
+  assert!(matches!(task.video_status, task::Status::Finish));
+
+  crate::utils::delete_code_dir(code).expect("Failed to delete code directory");
+  crate::database::delete_task_by_code(code).unwrap();
+}
+
+#[test]
+fn test_render_preview() {
+  let code = "renderpreview";
+  dotenv().ok();
+  crate::database::init_db();
+  let _ = crate::database::delete_task_by_code(code);
+  crate::database::insert_task(code, false, false).expect("Failed to insert task");
+  crate::database::update_task_status(code, task::Status::Finish).unwrap();
+  crate::database::update_video_status(code, task::Status::Finish).unwrap();
+  crate::utils::create_code_dir(code).expect("Failed to create code directory");
+
+  let (gen_sender, _gen_rx) = mpsc::channel(1);
+  let (merge_sender, _merge_rx) = mpsc::channel(1);
+  let (rerender_sender, _rerender_rx) = mpsc::channel(1);
+  let request = worker::GenVideoRequest {
+    code: code.to_string(),
+    layout: Form::<avatar::Layout>::parse("x=0.5&y=0.5&shape=circle").unwrap(),
+    narration: false,
+    script: None,
+    slideshow: None,
+    remove_bg: false,
+    background: avatar::Background {
+      mode: avatar::BackgroundMode::Transparent,
+      color: "#00FF00".to_string(),
+      image: None,
+    },
+    audio: Form::<audio::AudioOptions>::parse("").unwrap(),
+    preview: Some(10),
+    subtitle: false,
+    transcription: Form::<transcription::TranscriptionOptions>::parse("").unwrap(),
+    subtitle_format: Form::<subtitle::FormatOptions>::parse("").unwrap(),
+    chapters: Form::<chapter::ChapterOptions>::parse("").unwrap(),
+  };
+  gen_sender.try_send(request.clone()).unwrap();
+  let sender = worker::Sender {
+    gen_sender,
+    merge_sender,
+    rerender_sender,
+  };
+  let rocket = rocket::build().mount("/", routes![render]).manage(sender);
+  let client = Client::untracked(rocket).expect("valid rocket instance");
+
+  // 不是預覽任務
+  let response = client.post(format!("/api/gen/{}/render", code)).dispatch();
+  assert_eq!(response.status(), Status::Conflict);
+
+  // 佇列已滿時仍保留預覽
+  crate::database::update_task_render_request(code, Some(&request)).unwrap();
+  crate::utils::create_file(code, constant::SOURCE_VIDEO_FILE).expect("Failed to create file");
+  let response = client.post(format!("/api/gen/{}/render", code)).dispatch();
+  assert_eq!(response.status(), Status::ServiceUnavailable);
+  let task = crate::database::get_task_info(code).unwrap();
+  assert!(matches!(task.status, task::Status::Finish));
+  let preview = crate::database::get_render_request(code).unwrap();
+  assert_eq!(preview.and_then(|preview| preview.preview), Some(10));
+
+  crate::utils::delete_code_dir(code).expect("Failed to delete code directory");
+  crate::database::delete_task_by_code(code).unwrap();
+}
+
+#[test]
+fn test_get_transcript_signed() {
+  let code = "transcriptlink";
+  dotenv().ok();
+  crate::database::init_db();
+  let _ = crate::database::delete_task_by_code(code);
+  crate::database::insert_task(code, true, false).expect("Failed to insert task");
+  let subtitles = vec![subtitle::Subtitle::new(
+    "hello",
+    "00:00:00,000",
+    "00:00:01,000",
+  )];
+  crate::database::update_task_subtitles(code, &subtitles, "alice", None).unwrap();
+
+  let rocket = rocket::build().mount("/", routes![get_transcript]);
+  let client = Client::untracked(rocket).expect("valid rocket instance");
+
+  // 未簽章
+  let response = client
+    .get(format!("/api/tasks/{}/transcript?format=md", code))
+    .dispatch();
+  assert_eq!(response.status(), Status::Forbidden);
+
+  // 簽給其他資源的連結
+  let link = crate::signer::sign_download(code, constant::DOWNLOAD_RESOURCE, 60, false).unwrap();
+  let response = client
+    .get(format!("/api/tasks/{}/transcript?{}", code, link.query()))
+    .dispatch();
+  assert_eq!(response.status(), Status::Forbidden);
+
+  let link = crate::signer::sign_download(code, "transcript", 60, false).unwrap();
+  let response = client
+    .get(format!(
+      "/api/tasks/{}/transcript?format=md&{}",
+      code,
+      link.query()
+    ))
+    .dispatch();
+  assert_eq!(response.status(), Status::Ok);
+  assert!(response.into_string().unwrap().contains("hello"));
+
+  crate::database::delete_task_by_code(code).unwrap();
+}
+
+#[test]
+fn test_single_use_range_download() {
+  let code = "singlerange";
+  dotenv().ok();
+  crate::database::init_db();
+  let _ = crate::database::delete_task_by_code(code);
+  crate::database::insert_task(code, false, false).expect("Failed to insert task");
+  crate::utils::create_code_dir(code).expect("Failed to create code directory");
+  let path = crate::utils::create_file(code, constant::RESULT_FILE).expect("Failed to create file");
+  std::fs::write(&path, "0123456789").unwrap();
+
+  let link = crate::signer::sign_download(code, constant::DOWNLOAD_RESOURCE, 60, true).unwrap();
+  let url = format!("/download/{}?{}", code, link.query());
+  let rocket = rocket::build().mount("/", routes![download]);
+  let client = Client::untracked(rocket).expect("valid rocket instance");
+  let ip = "10.0.0.1:8000".parse().unwrap();
+
+  let response = client.get(&url).remote(ip).dispatch();
+  assert_eq!(response.status(), Status::Ok);
+
+  // 從頭開始的Range等同再次下載
+  let response = client
+    .get(&url)
+    .remote(ip)
+    .header(Header::new("Range", "bytes=0-"))
+    .dispatch();
+  assert_eq!(response.status(), Status::Forbidden);
+
+  // If-Range不符時會回傳完整檔案
+  let response = client
+    .get(&url)
+    .remote(ip)
+    .header(Header::new("Range", "bytes=5-"))
+    .header(Header::new("If-Range", "\"stale\""))
+    .dispatch();
+  assert_eq!(response.status(), Status::Forbidden);
+
+  let response = client
+    .get(&url)
+    .remote("10.0.0.2:8000".parse().unwrap())
+    .header(Header::new("Range", "bytes=5-"))
+    .dispatch();
+  assert_eq!(response.status(), Status::Forbidden);
+
+  // 同一個使用者的續傳
+  let response = client
+    .get(&url)
+    .remote(ip)
+    .header(Header::new("Range", "bytes=5-"))
+    .dispatch();
+  assert_eq!(response.status(), Status::PartialContent);
+  assert_eq!(response.into_string().unwrap(), "56789");
+
+  crate::utils::delete_code_dir(code).expect("Failed to delete code directory");
+  crate::database::delete_task_by_code(code).unwrap();
+}
+
+#[test]
+fn test_format_subtitle_not_found() {
+  dotenv().ok();
+  crate::database::init_db();
+  let rocket = rocket::build().mount("/", routes![format_subtitle]);
+  let client = Client::untracked(rocket).expect("valid rocket instance");
+
+  let response = client
+    .post("/api/subtitle/missingcode/format")
+    .header(ContentType::Form)
+    .body("max_chars_per_line=42")
+    .dispatch();
+  assert_eq!(response.status(), Status::NotFound);
+}
+
+#[test]
+fn test_upload_font_unauthorized() {
+  dotenv().ok();
+  let rocket = rocket::build().mount("/", routes![upload_font]);
+  let client = Client::untracked(rocket).expect("valid rocket instance");
+
+  // 未通過驗證時在讀取上傳資料前就拒絕
+  let response = client
+    .post("/api/fonts")
+    .header(ContentType::new("multipart", "form-data").with_params(("boundary", "font")))
+    .body("not multipart")
+    .dispatch();
+  assert!([Status::Unauthorized, Status::Forbidden].contains(&response.status()));
+}
+
+#[test]
+fn test_list_translations_not_found() {
+  dotenv().ok();
+  crate::database::init_db();
+  let rocket = rocket::build().mount("/", routes![list_translations]);
+  let client = Client::untracked(rocket).expect("valid rocket instance");
+
+  let response = client
+    .get("/api/subtitle/missingcode/translations")
+    .dispatch();
+  assert_eq!(response.status(), Status::NotFound);
+}
+
+#[test]
+fn test_set_chapters_unedited_subtitles() {
+  let code = "chaptersrc";
+  dotenv().ok();
+  crate::database::init_db();
+  let _ = crate::database::delete_task_by_code(code);
+  crate::database::insert_task(code, false, true).expect("Failed to insert task");
+  crate::database::update_task_status(code, task::Status::Finish).unwrap();
+  crate::database::update_video_status(code, task::Status::Finish).unwrap();
+  crate::utils::create_code_dir(code).expect("Failed to create code directory");
+  crate::utils::create_file(code, constant::RESULT_FILE).expect("Failed to create file");
+
+  let (gen_sender, _gen_rx) = mpsc::channel(1);
+  let (merge_sender, mut merge_rx) = mpsc::channel(1);
+  let (rerender_sender, _rerender_rx) = mpsc::channel(1);
+  let sender = worker::Sender {
+    gen_sender,
+    merge_sender,
+    rerender_sender,
+  };
+  let rocket = rocket::build()
+    .mount("/", routes![set_chapters])
+    .manage(sender);
+  let client = Client::untracked(rocket).expect("valid rocket instance");
+
+  // 沒有編輯過字幕時仍送出合成請求
+  let response = client
+    .post(format!("/api/gen/{}/chapters", code))
+    .header(ContentType::Form)
+    .body("chapters[0].start_time=00:00:00,000&chapters[0].end_time=00:00:05,000&chapters[0].title=Intro")
+    .dispatch();
+  assert_eq!(response.status(), Status::Ok);
+  assert_eq!(merge_rx.try_recv().unwrap().code, code);
+
+  // 沒有任何字幕時只寫入章節，有Whisper產生的srt時使用srt
+  assert!(matches!(
+    crate::controller::subtitle_source(code).unwrap(),
+    crate::controller::SubtitleSource::None
+  ));
+  crate::utils::create_file(code, constant::SUBS_FILE).expect("Failed to create file");
+  assert!(matches!(
+    crate::controller::subtitle_source(code).unwrap(),
+    crate::controller::SubtitleSource::File
+  ));
+
+  crate::utils::delete_code_dir(code).expect("Failed to delete code directory");
+  crate::database::delete_task_by_code(code).unwrap();
+}
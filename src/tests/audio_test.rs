@@ -0,0 +1,98 @@
+use super::common::parse_options;
+use crate::model::{audio::*, constant::*, video::*};
+use rocket::{
+  form::{self, Form},
+  fs::TempFile,
+  http::{ContentType, Status},
+  local::blocking::Client,
+  post, routes, FromForm,
+};
+
+fn parse(query: &str) -> form::Result<'_, AudioOptions> {
+  parse_options("audio_processing", query)
+}
+
+#[test]
+fn test_parse_audio_options() {
+  let options = parse("").unwrap();
+  assert!(!options.normalize);
+  assert_eq!(options.target_lufs, -16.0);
+  assert_eq!(options.noise_reduction, NoiseReduction::Off);
+  assert!(!options.is_enabled());
+
+  let options = parse(
+    "audio_processing.normalize=true&audio_processing.target_lufs=-23&audio_processing.noise_reduction=strong",
+  )
+  .unwrap();
+  assert!(options.normalize);
+  assert_eq!(options.target_lufs, -23.0);
+  assert_eq!(options.noise_reduction, NoiseReduction::Strong);
+  assert!(options.is_enabled());
+
+  let options = parse("audio_processing.noise_reduction=light").unwrap();
+  assert!(options.is_enabled());
+}
+
+#[test]
+fn test_parse_audio_options_invalid() {
+  assert!(parse("audio_processing.target_lufs=0").is_err());
+  assert!(parse("audio_processing.target_lufs=-80").is_err());
+  assert!(parse("audio_processing.noise_reduction=max").is_err());
+}
+
+#[derive(FromForm)]
+struct Upload<'r> {
+  #[field(validate = validate_audio())]
+  audio: Option<TempFile<'r>>,
+}
+
+#[post("/audio", data = "<data>")]
+fn upload(data: Form<Upload<'_>>) -> Option<&'static str> {
+  data
+    .audio
+    .as_ref()
+    .and_then(|audio| narration_file(audio.content_type()))
+}
+
+fn upload_audio(client: &Client, content_type: &str) -> (Status, Option<String>) {
+  let boundary = "audio-boundary";
+  let body = format!(
+    "--{b}\r\nContent-Disposition: form-data; name=\"audio\"; filename=\"narration\"\r\nContent-Type: {}\r\n\r\nRIFF\r\n--{b}--\r\n",
+    content_type,
+    b = boundary
+  );
+  let response = client
+    .post("/audio")
+    .header(ContentType::new("multipart", "form-data").with_params(("boundary", boundary)))
+    .body(body)
+    .dispatch();
+  (response.status(), response.into_string())
+}
+
+#[test]
+fn test_validate_audio() {
+  let rocket = rocket::build().mount("/", routes![upload]);
+  let client = Client::untracked(rocket).expect("valid rocket instance");
+
+  // 保留旁白原本的副檔名
+  assert_eq!(
+    upload_audio(&client, "audio/wav"),
+    (Status::Ok, Some(NARRATION_WAV_FILE.to_string()))
+  );
+  assert_eq!(
+    upload_audio(&client, "audio/mpeg"),
+    (Status::Ok, Some(NARRATION_MP3_FILE.to_string()))
+  );
+  assert_eq!(
+    upload_audio(&client, "audio/x-m4a"),
+    (Status::Ok, Some(NARRATION_M4A_FILE.to_string()))
+  );
+  assert_eq!(
+    upload_audio(&client, "video/mp4").0,
+    Status::UnprocessableEntity
+  );
+  assert_eq!(
+    upload_audio(&client, "audio/ogg").0,
+    Status::UnprocessableEntity
+  );
+}
@@ -0,0 +1,77 @@
+use crate::model::{avatar::*, constant::*};
+use rocket::{form::Form, http::ContentType};
+
+#[test]
+fn test_parse_layout() {
+  let layout =
+    Form::<Layout>::parse("x=0.5&y=0.25&shape=rounded_rectangle&scale=0.3&anchor=bottom_right")
+      .expect("Failed to parse layout");
+
+  assert_eq!(layout.shape, Shape::RoundedRectangle);
+  assert_eq!(layout.anchor, Anchor::BottomRight);
+  assert_eq!(layout.scale, Some(0.3));
+  assert_eq!(layout.border_color, "#FFFFFF");
+  assert_eq!(layout.border_width, 0);
+}
+
+#[test]
+fn test_parse_layout_invalid() {
+  assert!(Form::<Layout>::parse("x=0.5&y=0.5&shape=hexagon").is_err());
+  assert!(Form::<Layout>::parse("x=0.5&y=0.5&shape=circle&border_color=white").is_err());
+  assert!(Form::<Layout>::parse("x=0.5&y=0.5&shape=circle&border_width=50").is_err());
+  assert!(Form::<Layout>::parse("x=0.5&y=0.5&shape=circle&scale=2").is_err());
+  assert!(Form::<Layout>::parse("x=0.5&y=0.5&shape=circle&margin_x=0.8").is_err());
+  assert!(Form::<Layout>::parse("x=0.5&y=0.5&shape=circle&anchor=center").is_err());
+}
+
+#[test]
+fn test_check_timeline() {
+  let timeline = Form::<TimelineRequest>::parse(
+    "timeline[0].start_time=00:00:00,000&timeline[0].end_time=00:00:05,000&timeline[0].x=0.1&timeline[0].y=0.1\
+     &timeline[1].start_time=00:00:05,000&timeline[1].end_time=00:00:10,000&timeline[1].x=0&timeline[1].y=0&timeline[1].visible=false",
+  )
+  .expect("Failed to parse timeline")
+  .timeline;
+
+  assert_eq!(timeline.len(), 2);
+  assert!(timeline[0].visible);
+  assert!(!timeline[1].visible);
+  assert!(check_timeline(&timeline, Some(10_000)).is_ok());
+  assert!(check_timeline(&timeline, Some(9_000)).is_err());
+}
+
+#[test]
+fn test_check_timeline_invalid() {
+  // 區段重疊
+  assert!(Form::<TimelineRequest>::parse(
+    "timeline[0].start_time=00:00:00&timeline[0].end_time=00:00:05&timeline[0].x=0&timeline[0].y=0\
+     &timeline[1].start_time=00:00:04&timeline[1].end_time=00:00:06&timeline[1].x=0&timeline[1].y=0",
+  )
+  .is_err());
+  // 結束早於開始
+  assert!(Form::<TimelineRequest>::parse(
+    "timeline[0].start_time=00:00:05&timeline[0].end_time=00:00:01&timeline[0].x=0&timeline[0].y=0",
+  )
+  .is_err());
+}
+
+#[test]
+fn test_validate_background() {
+  assert!(validate_color("#00ff00").is_ok());
+  assert!(validate_color("00FF00").is_err());
+  assert!(validate_color("#00FF0").is_err());
+  assert!(validate_color("#GGGGGG").is_err());
+  assert!(validate_scale(&None).is_ok());
+  assert!(validate_scale(&Some(0.01)).is_err());
+  assert!(validate_margin(&0.5).is_ok());
+  assert!(validate_margin(&-0.1).is_err());
+
+  assert_eq!(
+    background_file(Some(&ContentType::JPEG)),
+    BACKGROUND_JPG_FILE
+  );
+  assert_eq!(
+    background_file(Some(&ContentType::PNG)),
+    BACKGROUND_PNG_FILE
+  );
+}
@@ -0,0 +1,122 @@
+use super::common::parse_options;
+use crate::{
+  chapters::*,
+  model::{chapter::*, subtitle::Subtitle},
+};
+use rocket::form;
+
+fn parse(query: &str) -> form::Result<'_, ChapterOptions> {
+  parse_options("chapters", query)
+}
+
+fn chapter(start_time: &str, end_time: &str, title: &str) -> Chapter {
+  Chapter {
+    start_time: start_time.to_string(),
+    end_time: end_time.to_string(),
+    title: title.to_string(),
+  }
+}
+
+#[test]
+fn test_build_chapters() {
+  let subtitles = vec![
+    Subtitle::new("Welcome to\nthe course", "00:00:01,000", "00:00:03,000"),
+    Subtitle::new("Gradient descent", "00:01:05,000", "00:01:08,000"),
+  ];
+  let chapters = build_chapters(
+    &[130_000, 60_000, 60_000, 0, 200_000],
+    180_000,
+    30_000,
+    &subtitles,
+  );
+  assert_eq!(
+    chapters,
+    vec![
+      chapter("00:00:00,000", "00:01:00,000", "Welcome to the course"),
+      chapter("00:01:00,000", "00:02:10,000", "Gradient descent"),
+      chapter("00:02:10,000", "00:03:00,000", "Chapter 3"),
+    ]
+  );
+
+  // 距離前一個切點或結尾不足min_duration的切點略過
+  let chapters = build_chapters(&[10_000, 60_000, 70_000, 170_000], 180_000, 30_000, &[]);
+  assert_eq!(
+    chapters,
+    vec![
+      chapter("00:00:00,000", "00:01:00,000", "Chapter 1"),
+      chapter("00:01:00,000", "00:03:00,000", "Chapter 2"),
+    ]
+  );
+}
+
+#[test]
+fn test_chapters_to_vtt() {
+  let chapters = vec![
+    chapter("00:00:00,000", "00:01:00,000", "Intro"),
+    chapter("00:01:00,000", "00:02:00,500", "Summary"),
+  ];
+  assert_eq!(
+    to_vtt(&chapters),
+    "WEBVTT\n\n1\n00:00:00.000 --> 00:01:00.000\nIntro\n\n2\n00:01:00.000 --> 00:02:00.500\nSummary\n\n"
+  );
+
+  // 標題不可破壞cue結構
+  let chapters = vec![
+    chapter(
+      "00:00:00,000",
+      "00:01:00,000",
+      "Intro\n\n2\n00:00 --> 00:01",
+    ),
+    chapter("00:01:00,000", "00:02:00,000", " \n "),
+  ];
+  assert_eq!(
+    to_vtt(&chapters),
+    "WEBVTT\n\n1\n00:00:00.000 --> 00:01:00.000\nIntro 2 00:00 → 00:01\n\n2\n00:01:00.000 --> 00:02:00.000\nChapter 2\n\n"
+  );
+}
+
+#[test]
+fn test_check_chapters() {
+  let chapters = vec![
+    chapter("00:00:00,000", "00:01:00,000", "Intro"),
+    chapter("00:01:00,000", "00:02:00,000", "Summary"),
+  ];
+  assert!(check_chapters(&chapters, Some(120_000)).is_ok());
+  assert!(check_chapters(&chapters, Some(90_000)).is_err());
+
+  let overlapping = vec![
+    chapter("00:00:00,000", "00:01:00,000", "Intro"),
+    chapter("00:00:30,000", "00:02:00,000", "Summary"),
+  ];
+  assert!(check_chapters(&overlapping, None).is_err());
+  let reversed = vec![chapter("00:01:00,000", "00:00:00,000", "Intro")];
+  assert!(check_chapters(&reversed, None).is_err());
+}
+
+#[test]
+fn test_parse_chapter_options() {
+  let options = parse("").unwrap();
+  assert!(!options.enabled);
+  assert_eq!(options.threshold, 0.3);
+  assert_eq!(options.min_duration, 30);
+
+  assert!(parse("chapters.threshold=0").is_err());
+  assert!(parse("chapters.min_duration=0").is_err());
+}
+
+#[test]
+fn test_check_chapters_messages() {
+  let overlapping = vec![
+    chapter("00:00:00,000", "00:01:00,000", "Intro"),
+    chapter("00:00:30,000", "00:02:00,000", "Summary"),
+  ];
+  assert_eq!(
+    check_chapters(&overlapping, None).unwrap_err(),
+    "Chapter 1 overlaps the previous chapter"
+  );
+  let chapters = vec![chapter("00:00:00,000", "00:01:00,000", "Intro")];
+  assert_eq!(
+    check_chapters(&chapters, Some(30_000)).unwrap_err(),
+    "Chapter 0 exceeds the video duration"
+  );
+}
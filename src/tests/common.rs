@@ -2,6 +2,7 @@ pub use crate::model::*;
 use crate::{database, utils};
 use chrono::NaiveDate;
 use dotenv::dotenv;
+use rocket::form::{self, Form, FromForm};
 use rusqlite::{params, Connection};
 use std::{
   env,
@@ -108,20 +109,29 @@ pub fn create_form_data(
   }
 
   // x and y fields
-  write!(data, "Content-Disposition: form-data; name=\"x\"\r\n")?;
+  write!(
+    data,
+    "Content-Disposition: form-data; name=\"layout.x\"\r\n"
+  )?;
   write!(data, "\r\n")?;
   write!(data, "{}", x)?;
   write!(data, "\r\n")?;
 
   write!(data, "--{}\r\n", boundary)?;
-  write!(data, "Content-Disposition: form-data; name=\"y\"\r\n")?;
+  write!(
+    data,
+    "Content-Disposition: form-data; name=\"layout.y\"\r\n"
+  )?;
   write!(data, "\r\n")?;
   write!(data, "{}", y)?;
   write!(data, "\r\n")?;
 
   // shape field
   write!(data, "--{}\r\n", boundary)?;
-  write!(data, "Content-Disposition: form-data; name=\"shape\"\r\n")?;
+  write!(
+    data,
+    "Content-Disposition: form-data; name=\"layout.shape\"\r\n"
+  )?;
   write!(data, "\r\n")?;
   write!(data, "{}", shape)?;
   write!(data, "\r\n")?;
@@ -142,6 +152,14 @@ pub fn create_form_data(
   Ok(data)
 }
 
+// 解析表單中巢狀於name下的選項，例如generation.size=512
+pub fn parse_options<'r, T: FromForm<'r>>(name: &str, query: &'r str) -> form::Result<'r, T> {
+  let fields = Form::values(query)
+    .filter(|field| field.name.key_lossy().as_str() == name)
+    .map(|field| field.shift());
+  Form::<T>::parse_iter(fields)
+}
+
 pub fn insert_task_with_status(code: &str, status: task::Status) {
   database::init_db();
   let conn = Connection::open("./slidetalker.db3").expect("Failed to open ./slidetalker.db3");
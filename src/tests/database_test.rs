@@ -9,3 +9,51 @@
 //   let result = conn.execute("DELETE FROM task WHERE code = ?1", params![code]);
 //   assert!(result.is_ok());
 // }
+
+use crate::{database, model::subtitle::Subtitle};
+use dotenv::dotenv;
+
+#[test]
+fn test_subtitle_revisions() {
+  let code = "revisions";
+  dotenv().ok();
+  database::init_db();
+  let _ = database::delete_task_by_code(code);
+  database::insert_task(code, true, false).expect("Failed to insert task");
+
+  let original = vec![Subtitle::new("helo", "00:00:00,000", "00:00:01,000")];
+  let edited = vec![Subtitle::new("hello", "00:00:00,000", "00:00:01,000")];
+  database::insert_original_revision(code, &original, "whisper").unwrap();
+  assert_eq!(
+    database::update_task_subtitles(code, &edited, "alice", Some(0)).unwrap(),
+    Some(1)
+  );
+  // 版本不符時不寫入
+  assert_eq!(
+    database::update_task_subtitles(code, &edited, "carol", Some(0)).unwrap(),
+    None
+  );
+  assert_eq!(
+    database::update_task_subtitles(code, &original, "bob", None).unwrap(),
+    Some(2)
+  );
+  assert_eq!(database::get_latest_revision(code).unwrap(), Some(2));
+  database::insert_original_revision(code, &edited, "whisper").unwrap();
+
+  let revisions = database::get_revisions(code).unwrap();
+  assert_eq!(revisions.len(), 3);
+  assert_eq!(revisions[0].author, "whisper");
+  assert_eq!(revisions[1].author, "alice");
+  assert_eq!(
+    database::get_revision_subtitles(code, 0).unwrap(),
+    Some(original)
+  );
+  assert_eq!(
+    database::get_revision_subtitles(code, 1).unwrap(),
+    Some(edited)
+  );
+  assert_eq!(database::get_revision_subtitles(code, 3).unwrap(), None);
+
+  database::delete_task_by_code(code).unwrap();
+  assert!(database::get_revisions(code).unwrap().is_empty());
+}
@@ -0,0 +1,29 @@
+use crate::{database, export::*, model::constant::*, utils::*};
+use dotenv::dotenv;
+use std::fs;
+
+#[test]
+fn test_bundle_cache() {
+  let code = "bundle";
+  dotenv().ok();
+  database::init_db();
+  let _ = database::delete_task_by_code(code);
+  database::insert_task(code, false, false).expect("Failed to insert task");
+  create_code_dir(code).expect("Failed to create code directory");
+  let result = create_file(code, RESULT_FILE).expect("Failed to create file");
+  fs::write(&result, b"video").expect("Failed to write file");
+
+  let bundle = build_bundle(code).unwrap().expect("Missing bundle");
+  let built = fs::metadata(&bundle).unwrap().modified().unwrap();
+  // 檔案未變時沿用同一個bundle
+  build_bundle(code).unwrap();
+  assert_eq!(fs::metadata(&bundle).unwrap().modified().unwrap(), built);
+
+  fs::write(&result, b"new video").expect("Failed to write file");
+  let size = fs::metadata(&bundle).unwrap().len();
+  build_bundle(code).unwrap();
+  assert!(fs::metadata(&bundle).unwrap().len() > size);
+
+  delete_code_dir(code).expect("Failed to delete code directory");
+  database::delete_task_by_code(code).unwrap();
+}
@@ -0,0 +1,79 @@
+use crate::fonts::*;
+use std::{env, fs};
+
+// 只有name table的最小字型檔，family與style以Windows平台UTF-16BE儲存
+fn build_font(family: &str, style: &str) -> Vec<u8> {
+  let encode = |s: &str| -> Vec<u8> { s.encode_utf16().flat_map(|u| u.to_be_bytes()).collect() };
+  let (family, style) = (encode(family), encode(style));
+
+  let mut name = Vec::new();
+  name.extend_from_slice(&[0, 0, 0, 2, 0, 30]);
+  for (name_id, offset, length) in [(1u16, 0, family.len()), (2, family.len(), style.len())] {
+    for value in [3u16, 1, 0x0409, name_id, length as u16, offset as u16] {
+      name.extend_from_slice(&value.to_be_bytes());
+    }
+  }
+  name.extend_from_slice(&family);
+  name.extend_from_slice(&style);
+
+  let mut font = vec![0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0];
+  font.extend_from_slice(b"name");
+  font.extend_from_slice(&[0, 0, 0, 0]);
+  font.extend_from_slice(&28u32.to_be_bytes());
+  font.extend_from_slice(&(name.len() as u32).to_be_bytes());
+  font.extend_from_slice(&name);
+  font
+}
+
+#[test]
+fn test_read_font_names() {
+  let dir = env::temp_dir().join("fonts_names_test");
+  fs::create_dir_all(&dir).unwrap();
+  let path = dir.join("font.ttf");
+
+  let font = build_font("Noto Sans CJK TC", "Bold");
+  fs::write(&path, &font).unwrap();
+  assert_eq!(
+    read_font_names(&path),
+    Some((String::from("Noto Sans CJK TC"), String::from("Bold")))
+  );
+  fs::write(&path, b"not a font").unwrap();
+  assert_eq!(read_font_names(&path), None);
+  fs::write(&path, &font[..40]).unwrap();
+  assert_eq!(read_font_names(&path), None);
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_is_valid_id() {
+  assert!(is_valid_id("NotoSansCJK-Regular"));
+  assert!(!is_valid_id("../NotoSansCJK-Regular"));
+  assert!(!is_valid_id("fonts/NotoSansCJK"));
+  assert!(!is_valid_id(""));
+}
+
+#[test]
+fn test_font_registry() {
+  // 使用暫存目錄，避免寫入實際的字型目錄
+  let dir = env::temp_dir().join("fonts_test");
+  let dir = dir.to_str().unwrap();
+  fs::create_dir_all(dir).unwrap();
+  fs::write(
+    format!("{}/TestFont-Italic.ttf", dir),
+    build_font("Test Font", "Italic"),
+  )
+  .unwrap();
+
+  assert!(font_path_in(dir, "TestFont-Italic").is_some());
+  assert!(font_path_in(dir, "MissingFont").is_none());
+  assert!(resolve_in(dir, "./TestFont-Italic.ttf").is_some());
+  assert!(resolve_in(dir, "/etc/passwd").is_none());
+
+  let fonts = list_fonts_in(dir).unwrap();
+  let font = fonts.iter().find(|f| f.id == "TestFont-Italic").unwrap();
+  assert_eq!(font.family, "Test Font");
+  assert_eq!(font.style, "Italic");
+
+  fs::remove_file(format!("{}/TestFont-Italic.ttf", dir)).unwrap();
+}
@@ -0,0 +1,119 @@
+use crate::{
+  database,
+  formatter::*,
+  model::{
+    constant::SUBS_FILE,
+    subtitle::{FormatOptions, Subtitle, Word},
+  },
+  subs::to_srt,
+  utils,
+};
+use dotenv::dotenv;
+use std::fs;
+
+fn options(max_chars_per_line: usize, max_lines: usize) -> FormatOptions {
+  FormatOptions {
+    enabled: true,
+    max_chars_per_line,
+    max_lines,
+    max_cps: 17.0,
+  }
+}
+
+#[test]
+fn test_text_width() {
+  assert_eq!(text_width("hello world"), 10);
+  assert_eq!(text_width("你好，world"), 11);
+}
+
+#[test]
+fn test_wrap_text() {
+  assert_eq!(
+    wrap_text("the quick brown fox jumps", 10),
+    vec!["the quick", "brown fox", "jumps"]
+  );
+  // CJK逐字斷行，標點不會出現在行首
+  assert_eq!(
+    wrap_text("今天天氣很好，我們去公園", 10),
+    vec!["今天天氣很", "好，我們去", "公園"]
+  );
+  assert_eq!(wrap_text("今天天氣很，好", 10), vec!["今天天氣", "很，好"]);
+  assert_eq!(wrap_text("用Rust寫程式", 7), vec!["用Rust", "寫程式"]);
+}
+
+#[test]
+fn test_format_subtitles() {
+  let mut subtitle = Subtitle::new(
+    "one two three four five six",
+    "00:00:00,000",
+    "00:00:06,000",
+  );
+  subtitle.words = ["one", "two", "three", "four", "five", "six"]
+    .iter()
+    .enumerate()
+    .map(|(i, text)| Word {
+      text: text.to_string(),
+      start_time: format!("00:00:0{},000", i),
+      end_time: format!("00:00:0{},900", i),
+    })
+    .collect();
+
+  let (subtitles, warnings) = format_subtitles(&[subtitle.clone()], &options(8, 2));
+  assert!(warnings.is_empty());
+  assert_eq!(subtitles.len(), 2);
+  assert_eq!(subtitles[0].text, "one two\nthree");
+  assert_eq!(subtitles[1].text, "four\nfive six");
+  assert_eq!(subtitles[0].end_time, "00:00:03,000");
+  assert_eq!(subtitles[1].start_time, "00:00:03,000");
+  assert_eq!(subtitles[1].end_time, "00:00:06,000");
+  assert_eq!(subtitles[0].words.len(), 3);
+  assert_eq!(subtitles[1].words.len(), 3);
+
+  let (subtitles, _) = format_subtitles(&[subtitle], &options(42, 2));
+  assert_eq!(subtitles.len(), 1);
+  assert_eq!(subtitles[0].words.len(), 6);
+}
+
+#[test]
+fn test_check_reading_speed() {
+  let subtitles = vec![
+    Subtitle::new("short", "00:00:00,000", "00:00:02,000"),
+    Subtitle::new("這是一段很長而且很快的字幕", "00:00:02,000", "00:00:03,000"),
+  ];
+
+  let warnings = check_reading_speed(&subtitles, 17.0);
+  assert_eq!(warnings.len(), 1);
+  assert_eq!(warnings[0].index, 1);
+  assert_eq!(warnings[0].cps, 26.0);
+}
+
+#[test]
+fn test_format_subtitle_file_warnings() {
+  let code = "readingspeed";
+  dotenv().ok();
+  database::init_db();
+  let _ = database::delete_task_by_code(code);
+  database::insert_task(code, true, false).expect("Failed to insert task");
+  utils::create_code_dir(code).expect("Failed to create code directory");
+  let subtitles = vec![Subtitle::new(
+    "這是一段很長而且很快的字幕",
+    "00:00:00,000",
+    "00:00:01,000",
+  )];
+  let subs_path = utils::create_file(code, SUBS_FILE).unwrap();
+  fs::write(subs_path, to_srt(&subtitles)).unwrap();
+
+  format_subtitle_file(code, &options(42, 2)).unwrap();
+  let warnings = database::get_reading_speed_warnings(code).unwrap();
+  assert_eq!(warnings.len(), 1);
+  assert_eq!(warnings[0].index, 0);
+
+  // 編輯後的字幕不再沿用自動斷行的警告
+  database::update_task_subtitles(code, &subtitles, "alice", None).unwrap();
+  assert!(database::get_reading_speed_warnings(code)
+    .unwrap()
+    .is_empty());
+
+  utils::delete_code_dir(code).expect("Failed to delete code directory");
+  database::delete_task_by_code(code).unwrap();
+}
@@ -0,0 +1,85 @@
+use super::common::parse_options;
+use crate::{database, model::generation::*};
+use dotenv::dotenv;
+use rocket::form;
+
+fn parse(query: &str) -> form::Result<'_, GenerationRequest> {
+  parse_options("generation", query)
+}
+
+#[test]
+fn test_parse_generation_options() {
+  let options = parse("").unwrap().resolve(None);
+  assert_eq!(options, GenerationOptions::default());
+  assert_eq!(options.preprocess, Preprocess::Crop);
+  assert_eq!(options.size, 256);
+
+  let options = parse(
+    "generation.preprocess=full&generation.still_mode=true&generation.face_enhancer=true&generation.pose_style=12&generation.expression_scale=1.5&generation.size=512",
+  )
+  .unwrap()
+  .resolve(None);
+  assert_eq!(options.preprocess, Preprocess::Full);
+  assert!(options.still_mode);
+  assert!(options.face_enhancer);
+  assert_eq!(options.pose_style, 12);
+  assert_eq!(options.expression_scale, 1.5);
+  assert_eq!(options.size, 512);
+}
+
+#[test]
+fn test_parse_generation_options_invalid() {
+  assert!(parse("generation.preprocess=zoom").is_err());
+  assert!(parse("generation.pose_style=46").is_err());
+  assert!(parse("generation.expression_scale=5").is_err());
+  assert!(parse("generation.size=300").is_err());
+}
+
+#[test]
+fn test_presets() {
+  assert!(Preset::Draft.generation().still_mode);
+  assert_eq!(Preset::Draft.encoding().height, Some(480));
+  assert_eq!(Preset::Standard.generation().size, 256);
+  assert!(Preset::High.generation().face_enhancer);
+  assert_eq!(Preset::High.generation().size, 512);
+  assert!(Preset::High.encoding().crf < Preset::Standard.encoding().crf);
+}
+
+#[test]
+fn test_preset_keeps_explicit_fields() {
+  let options = parse("generation.size=512&generation.pose_style=3").unwrap();
+  let applied = options.resolve(Some(Preset::Draft));
+  assert!(applied.still_mode);
+  assert_eq!(applied.size, 512);
+  assert_eq!(applied.pose_style, 3);
+  assert_eq!(
+    GenerationRequest::default().resolve(Some(Preset::High)),
+    Preset::High.generation()
+  );
+
+  // 明確指定與預設值相同時仍優先於preset
+  let options = parse("generation.size=256&generation.face_enhancer=false").unwrap();
+  let applied = options.resolve(Some(Preset::High));
+  assert_eq!(applied.size, 256);
+  assert!(!applied.face_enhancer);
+  assert_eq!(applied.preprocess, Preprocess::Full);
+}
+
+#[test]
+fn test_generation_options_persisted() {
+  let code = "generation";
+  dotenv().ok();
+  database::init_db();
+  let _ = database::delete_task_by_code(code);
+  database::insert_task(code, false, false).expect("Failed to insert task");
+  assert_eq!(database::get_generation_options(code).unwrap(), None);
+
+  let options = Preset::High.generation();
+  database::update_task_generation_options(code, &options).unwrap();
+  assert_eq!(
+    database::get_generation_options(code).unwrap(),
+    Some(options)
+  );
+
+  database::delete_task_by_code(code).unwrap();
+}
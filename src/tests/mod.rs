@@ -1,4 +1,21 @@
 mod api_test;
+mod audio_test;
+mod avatar_test;
+mod chapters_test;
 mod common;
 mod database_test;
+mod export_test;
+mod fonts_test;
+mod formatter_test;
+mod generation_test;
+mod range_test;
+mod search_test;
+mod signer_test;
+mod slideshow_test;
+mod subs_test;
 mod timer_test;
+mod transcription_test;
+mod translate_test;
+mod tts_test;
+mod utils_test;
+mod version_test;
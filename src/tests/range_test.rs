@@ -0,0 +1,143 @@
+use crate::range::*;
+use rocket::{
+  get,
+  http::{Header, Status},
+  local::blocking::Client,
+  routes,
+};
+use std::{env, fs};
+
+fn file_path() -> String {
+  let path = env::temp_dir().join("range_test.bin");
+  let data: Vec<u8> = (0..100).collect();
+  fs::write(&path, data).expect("Failed to write test file");
+  path.to_string_lossy().to_string()
+}
+
+#[get("/file")]
+async fn file() -> Option<RangedFile> {
+  RangedFile::open(file_path()).await.ok()
+}
+
+fn client() -> Client {
+  Client::untracked(rocket::build().mount("/", routes![file])).expect("valid rocket instance")
+}
+
+#[test]
+fn test_parse_single_range() {
+  assert_eq!(
+    parse_range("bytes=0-99", 1000),
+    RangeRequest::Partial(vec![(0, 99)])
+  );
+  assert_eq!(
+    parse_range("bytes=900-", 1000),
+    RangeRequest::Partial(vec![(900, 999)])
+  );
+  assert_eq!(
+    parse_range("bytes=-100", 1000),
+    RangeRequest::Partial(vec![(900, 999)])
+  );
+  assert_eq!(
+    parse_range("bytes=500-5000", 1000),
+    RangeRequest::Partial(vec![(500, 999)])
+  );
+}
+
+#[test]
+fn test_parse_multi_range() {
+  assert_eq!(
+    parse_range("bytes=0-9, 20-29", 1000),
+    RangeRequest::Partial(vec![(0, 9), (20, 29)])
+  );
+  // 重疊與相鄰的區段會被合併
+  assert_eq!(
+    parse_range("bytes=20-29,0-10,5-19", 1000),
+    RangeRequest::Partial(vec![(0, 29)])
+  );
+}
+
+#[test]
+fn test_parse_unsatisfiable_range() {
+  assert_eq!(
+    parse_range("bytes=1000-", 1000),
+    RangeRequest::Unsatisfiable
+  );
+  assert_eq!(parse_range("bytes=-0", 1000), RangeRequest::Unsatisfiable);
+  assert_eq!(parse_range("bytes=0-", 0), RangeRequest::Unsatisfiable);
+}
+
+#[test]
+fn test_parse_invalid_range() {
+  assert_eq!(parse_range("items=0-9", 1000), RangeRequest::Full);
+  assert_eq!(parse_range("bytes=9-0", 1000), RangeRequest::Full);
+  assert_eq!(parse_range("bytes=a-b", 1000), RangeRequest::Full);
+  assert_eq!(parse_range("bytes=-", 1000), RangeRequest::Full);
+}
+
+#[test]
+fn test_parse_large_multi_range() {
+  let len = 1024 * 1024 * 1024;
+  assert_eq!(parse_range("bytes=0-0,2-", len), RangeRequest::Full);
+  assert_eq!(
+    parse_range("bytes=0-0,2-", 100),
+    RangeRequest::Partial(vec![(0, 0), (2, 99)])
+  );
+}
+
+#[test]
+fn test_respond_partial() {
+  let client = client();
+  let response = client
+    .get("/file")
+    .header(Header::new("Range", "bytes=10-19"))
+    .dispatch();
+  assert_eq!(response.status(), Status::PartialContent);
+  assert_eq!(
+    response.headers().get_one("Content-Range"),
+    Some("bytes 10-19/100")
+  );
+  assert_eq!(response.into_bytes(), Some((10..20).collect::<Vec<u8>>()));
+}
+
+#[test]
+fn test_respond_unsatisfiable() {
+  let client = client();
+  let response = client
+    .get("/file")
+    .header(Header::new("Range", "bytes=200-"))
+    .dispatch();
+  assert_eq!(response.status(), Status::RangeNotSatisfiable);
+  assert_eq!(
+    response.headers().get_one("Content-Range"),
+    Some("bytes */100")
+  );
+}
+
+#[test]
+fn test_respond_if_range() {
+  let client = client();
+  let etag = client
+    .get("/file")
+    .dispatch()
+    .headers()
+    .get_one("ETag")
+    .expect("Missing ETag")
+    .to_string();
+
+  // ETag相符時回傳部分內容，不符時回傳完整檔案
+  let response = client
+    .get("/file")
+    .header(Header::new("Range", "bytes=0-9"))
+    .header(Header::new("If-Range", etag))
+    .dispatch();
+  assert_eq!(response.status(), Status::PartialContent);
+
+  let response = client
+    .get("/file")
+    .header(Header::new("Range", "bytes=0-9"))
+    .header(Header::new("If-Range", "\"stale\""))
+    .dispatch();
+  assert_eq!(response.status(), Status::Ok);
+  assert_eq!(response.headers().get_one("Content-Range"), None);
+  assert_eq!(response.into_bytes().map(|body| body.len()), Some(100));
+}
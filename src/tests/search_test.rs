@@ -0,0 +1,131 @@
+use crate::{
+  database,
+  model::{search::Query, subtitle::Subtitle},
+  search::*,
+};
+use dotenv::dotenv;
+use rocket::form::Form;
+use rusqlite::{params, Connection};
+
+#[test]
+fn test_fts_text() {
+  assert_eq!(fts_text("Hello world"), "Hello world");
+  assert_eq!(fts_text("機器學習 model"), "機 器 學 習 model");
+  assert_eq!(fts_text("使用GPU訓練"), "使 用 GPU 訓 練");
+}
+
+#[test]
+fn test_fts_query() {
+  assert_eq!(fts_query("  "), None);
+  assert_eq!(
+    fts_query("neural 學習"),
+    Some("\"neural\" AND \"學 習\"".to_string())
+  );
+  assert_eq!(fts_query("say\"hi"), Some("\"say\"\"hi\"".to_string()));
+
+  let query = Form::<Query>::parse("q=hello&code=abc").unwrap();
+  assert_eq!((query.limit, query.offset), (50, 0));
+  assert!(Form::<Query>::parse("q=hello&code=abc&limit=500").is_err());
+}
+
+#[test]
+fn test_clean_snippet() {
+  let snippet = format!(
+    "我 們 {}學{} {}習{} 模 型",
+    MARK_START, MARK_END, MARK_START, MARK_END
+  );
+  assert_eq!(clean_snippet(&snippet), "我們<mark>學習</mark>模型");
+  let snippet = format!("a {}neural{} net", MARK_START, MARK_END);
+  assert_eq!(clean_snippet(&snippet), "a <mark>neural</mark> net");
+  // 字幕中的HTML不可被當成標籤
+  let snippet = format!(
+    "<script>alert(\"x\")</script> {}R&D{} 's",
+    MARK_START, MARK_END
+  );
+  assert_eq!(
+    clean_snippet(&snippet),
+    "&lt;script&gt;alert(&quot;x&quot;)&lt;/script&gt; <mark>R&amp;D</mark> &#39;s"
+  );
+}
+
+#[test]
+fn test_search_subtitles() {
+  let code = "search1";
+  let other = "search2";
+  dotenv().ok();
+  database::init_db();
+  for code in [code, other] {
+    let _ = database::delete_task_by_code(code);
+    database::insert_task(code, true, false).expect("Failed to insert task");
+  }
+
+  let subs = vec![
+    Subtitle::new("今天介紹機器學習", "00:00:01,000", "00:00:03,000"),
+    Subtitle::new("Neural networks are fun", "00:00:03,000", "00:00:05,000"),
+  ];
+  database::insert_original_revision(code, &subs, "whisper").unwrap();
+  database::insert_original_revision(other, &subs, "whisper").unwrap();
+
+  let codes = vec![code.to_string()];
+  let hits = database::search_subtitles(&codes, &fts_query("學習").unwrap(), 50, 0).unwrap();
+  assert_eq!(hits.len(), 1);
+  assert_eq!(hits[0].0, code);
+  assert_eq!(hits[0].1.start_time, "00:00:01,000");
+  assert_eq!(hits[0].1.snippet, "今天介紹機器<mark>學習</mark>");
+
+  // 編輯後索引跟著更新
+  let edited = vec![Subtitle::new(
+    "Neural networks",
+    "00:00:03,000",
+    "00:00:05,000",
+  )];
+  // 分頁
+  let hits = database::search_subtitles(&codes, &fts_query("學習").unwrap(), 1, 1).unwrap();
+  assert!(hits.is_empty());
+
+  database::update_task_subtitles(code, &edited, "alice", None).unwrap();
+  let hits = database::search_subtitles(&codes, &fts_query("學習").unwrap(), 50, 0).unwrap();
+  assert!(hits.is_empty());
+  let hits = database::search_subtitles(&codes, &fts_query("neural").unwrap(), 50, 0).unwrap();
+  assert_eq!(hits[0].1.snippet, "<mark>Neural</mark> networks");
+
+  for code in [code, other] {
+    database::delete_task_by_code(code).unwrap();
+  }
+  let hits = database::search_subtitles(&codes, &fts_query("neural").unwrap(), 50, 0).unwrap();
+  assert!(hits.is_empty());
+}
+
+#[test]
+fn test_backfill_subtitle_index() {
+  let code = "search3";
+  dotenv().ok();
+  database::init_db();
+  let _ = database::delete_task_by_code(code);
+  database::insert_task(code, true, false).expect("Failed to insert task");
+
+  // 模擬建立索引前就存在的字幕
+  let subs = vec![Subtitle::new(
+    "<b>backfilled</b>",
+    "00:00:01,000",
+    "00:00:02,000",
+  )];
+  let conn = Connection::open("./slidetalker.db3").expect("Failed to open ./slidetalker.db3");
+  conn
+    .execute(
+      "UPDATE task SET subtitles = ?1 WHERE code = ?2",
+      params![serde_json::to_string(&subs).unwrap(), code],
+    )
+    .expect("Failed to update subtitles");
+
+  database::init_db();
+  let codes = vec![code.to_string()];
+  let hits = database::search_subtitles(&codes, &fts_query("backfilled").unwrap(), 50, 0).unwrap();
+  assert_eq!(hits.len(), 1);
+  assert_eq!(
+    hits[0].1.snippet,
+    "&lt;b&gt;<mark>backfilled</mark>&lt;/b&gt;"
+  );
+
+  database::delete_task_by_code(code).unwrap();
+}
@@ -0,0 +1,92 @@
+use super::common::*;
+use crate::{database, signer::*};
+use dotenv::dotenv;
+
+#[test]
+fn test_verify_signed_download() {
+  dotenv().ok();
+  let link = sign_download("signed", "download", 60, false).expect("Failed to sign download");
+
+  assert!(verify_download("signed", "download", link.expires, None, &link.signature).is_ok());
+  assert!(link.to_url().contains("/download/signed?expires="));
+}
+
+#[test]
+fn test_verify_tampered_download() {
+  dotenv().ok();
+  let link = sign_download("signed", "download", 60, false).expect("Failed to sign download");
+
+  assert!(verify_download("other", "download", link.expires, None, &link.signature).is_err());
+  assert!(verify_download(
+    "signed",
+    "download",
+    link.expires + 1,
+    None,
+    &link.signature
+  )
+  .is_err());
+  assert!(verify_download(
+    "signed",
+    "download",
+    link.expires,
+    Some("nonce"),
+    &link.signature
+  )
+  .is_err());
+  assert!(verify_download("signed", "download", link.expires, None, "zz").is_err());
+  // 連結不能用於其他資源
+  assert!(verify_download("signed", "bundle", link.expires, None, &link.signature).is_err());
+}
+
+#[test]
+fn test_verify_expired_download() {
+  dotenv().ok();
+  let link = sign_download("expired", "download", -1, false).expect("Failed to sign download");
+
+  assert!(verify_download("expired", "download", link.expires, None, &link.signature).is_err());
+}
+
+#[test]
+fn test_single_use_download() {
+  let code = "single";
+  dotenv().ok();
+  database::init_db();
+  let link = sign_download(code, "download", 60, true).expect("Failed to sign download");
+  let nonce = link.nonce.clone().expect("Single use link without nonce");
+
+  assert!(verify_download(
+    code,
+    "download",
+    link.expires,
+    Some(&nonce),
+    &link.signature
+  )
+  .is_ok());
+  assert!(consume_nonce(code, &nonce, Some("10.0.0.1"), false).is_ok());
+  assert!(consume_nonce(code, &nonce, Some("10.0.0.1"), false).is_err());
+  // 同一個使用者拖曳播放時的Range請求
+  assert!(consume_nonce(code, &nonce, Some("10.0.0.1"), true).is_ok());
+  assert!(consume_nonce(code, &nonce, Some("10.0.0.2"), true).is_err());
+  assert!(consume_nonce(code, &nonce, None, true).is_err());
+
+  delete_task_by_code(code);
+}
+
+#[test]
+fn test_artifact_link_url() {
+  dotenv().ok();
+  let link = sign_download("signed", "subtitles_vtt", 60, false).expect("Failed to sign download");
+
+  assert!(link
+    .to_url()
+    .contains("/api/tasks/signed/artifacts/subtitles_vtt?expires="));
+  assert!(verify_download(
+    "signed",
+    "subtitles_vtt",
+    link.expires,
+    None,
+    &link.signature
+  )
+  .is_ok());
+  assert!(verify_download("signed", "result", link.expires, None, &link.signature).is_err());
+}
@@ -0,0 +1,32 @@
+use crate::model::slideshow::*;
+
+#[test]
+fn test_validate_durations() {
+  assert!(validate_durations(&[]).is_ok());
+  assert!(validate_durations(&[1.5, 3600.0]).is_ok());
+  assert!(validate_durations(&[0.0]).is_err());
+  assert!(validate_durations(&[-1.0]).is_err());
+  assert!(validate_durations(&[3600.5]).is_err());
+}
+
+#[test]
+fn test_validate_timestamps() {
+  assert!(validate_timestamps(&[]).is_ok());
+  assert!(validate_timestamps(&[0.0, 4.5, 10.0]).is_ok());
+  assert!(validate_timestamps(&[1.0, 4.5]).is_err());
+  assert!(validate_timestamps(&[0.0, 4.5, 4.5]).is_err());
+}
+
+#[test]
+fn test_total_duration() {
+  let mut slideshow = Slideshow {
+    pdf: false,
+    slides: vec![String::from("slides/slide_000.png")],
+    durations: vec![],
+    timestamps: vec![0.0],
+  };
+  assert_eq!(slideshow.total_duration(), None);
+
+  slideshow.durations = vec![1.5, 2.25];
+  assert_eq!(slideshow.total_duration(), Some(3_750));
+}
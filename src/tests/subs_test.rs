@@ -0,0 +1,238 @@
+use crate::{
+  model::{
+    revision::Operation,
+    subtitle::{ShiftRequest, Subtitle},
+    transcript,
+  },
+  subs::*,
+};
+use rocket::form::Form;
+
+static SRT: &'static str = "1\r\n00:00:00,000 --> 00:00:01,500\r\nhello\r\n\r\n2\r\n00:00:02,000 --> 00:00:03,250\r\nworld\r\nagain\r\n";
+
+#[test]
+fn test_parse_srt() {
+  let subtitles = parse_srt(SRT);
+
+  assert_eq!(subtitles.len(), 2);
+  assert_eq!(subtitles[0].text, "hello");
+  assert_eq!(subtitles[1].text, "world\nagain");
+  assert_eq!(subtitles[1].start_time, "00:00:02,000");
+  assert_eq!(subtitles[1].end_time, "00:00:03,250");
+}
+
+#[test]
+fn test_export_subtitles() {
+  let subtitles = parse_srt(SRT);
+
+  assert_eq!(to_srt(&subtitles), SRT.replace("\r\n", "\n") + "\n");
+  assert!(to_vtt(&subtitles).starts_with("WEBVTT\n\n00:00:00.000 --> 00:00:01.500\nhello\n"));
+  assert_eq!(
+    to_transcript_document(&subtitles, &transcript::Query::default()),
+    "[00:00:00]\nhello world again\n"
+  );
+}
+
+#[test]
+fn test_parse_time() {
+  assert_eq!(parse_time("00:00:10,500"), Some(10_500));
+  assert_eq!(parse_time("01:02:03.4"), Some(3_723_400));
+  assert_eq!(parse_time("00:10"), Some(10_000));
+  assert_eq!(parse_time("00:61:00"), None);
+  assert_eq!(parse_time("abc"), None);
+  assert_eq!(format_time(3_723_400, ','), "01:02:03,400");
+}
+
+static WORDS: &'static str = r#"[[{"word": " hello", "start": 0.0, "end": 0.8}], [{"word": " world", "start": 2.0, "end": 2.4}, {"word": " again", "start": 2.6, "end": 3.25}]]"#;
+
+#[test]
+fn test_word_timestamps() {
+  let mut subtitles = parse_srt(SRT);
+  for (subtitle, words) in subtitles.iter_mut().zip(parse_words(WORDS).unwrap()) {
+    subtitle.words = words;
+  }
+
+  assert_eq!(subtitles[1].words.len(), 2);
+  assert_eq!(subtitles[1].words[1].start_time, "00:00:02,600");
+  assert!(to_vtt(&subtitles).contains("00:00:02.000 --> 00:00:03.250\nworld<00:00:02.600> again\n"));
+
+  let ass = to_ass(&subtitles, true);
+  assert!(ass.contains("Dialogue: 0,0:00:00.00,0:00:01.50,Default,,0,0,0,,{\\kf80}hello\n"));
+  assert!(ass.contains(
+    "Dialogue: 0,0:00:02.00,0:00:03.25,Default,,0,0,0,,{\\kf40}world{\\k20}{\\kf65} again\n"
+  ));
+
+  let ass = to_ass(&subtitles, false);
+  assert!(ass.contains(",,world\\Nagain\n"));
+  assert!(parse_words("not json").is_none());
+}
+
+#[test]
+fn test_ass_styles() {
+  let mut subtitles = parse_srt(SRT);
+  // 未註冊的字型使用預設的family名稱
+  for subtitle in subtitles.iter_mut() {
+    subtitle.font = String::from("MissingFont");
+  }
+  subtitles[0].text = String::from("{\\b1}hello}");
+  subtitles[1].color = String::from("#FF8000");
+  subtitles[1].fontsize = 40;
+
+  let ass = to_ass(&subtitles, false);
+  assert!(ass.contains("Style: Default,Noto Sans CJK TC,32,&H00FFFFFF,&H00FFFFFF,"));
+  assert!(ass.contains("Style: Style1,Noto Sans CJK TC,40,&H000080FF,&H000080FF,"));
+  assert!(ass.contains(",Default,,0,0,0,,\\{\\b1\\}hello\\}\n"));
+  assert!(ass.contains(",Style1,,0,0,0,,world\\Nagain\n"));
+
+  // 只有具逐字時間的字幕使用karaoke顏色
+  subtitles[1].color = String::from("white");
+  subtitles[1].fontsize = 32;
+  subtitles[1].words = parse_words(WORDS).unwrap().remove(1);
+  let ass = to_ass(&subtitles, true);
+  assert!(ass.contains("Style: Default,Noto Sans CJK TC,32,&H00FFFFFF,&H00FFFFFF,"));
+  assert!(ass.contains("Style: Style1,Noto Sans CJK TC,32,&H0000FFFF,&H00FFFFFF,"));
+  assert!(ass.contains(",Default,,0,0,0,,\\{\\b1\\}hello\\}\n"));
+  assert!(ass.contains(",Style1,,0,0,0,,{\\kf40}world"));
+}
+
+#[test]
+fn test_shift_subtitles() {
+  let mut subtitles = parse_srt(SRT);
+  shift_subtitles(&mut subtitles, -500, None, None).unwrap();
+  assert_eq!(subtitles[0].start_time, "00:00:00,000");
+  assert_eq!(subtitles[0].end_time, "00:00:01,000");
+  assert_eq!(subtitles[1].start_time, "00:00:01,500");
+
+  let mut subtitles = parse_srt(SRT);
+  shift_subtitles(&mut subtitles, 1_000, Some(1_000), None).unwrap();
+  assert_eq!(subtitles[0].start_time, "00:00:00,000");
+  assert_eq!(subtitles[1].start_time, "00:00:03,000");
+  assert_eq!(subtitles[1].end_time, "00:00:04,250");
+}
+
+#[test]
+fn test_shift_subtitles_collapsed() {
+  // 平移到0之前的字幕被移除，部分落在0之前的字幕從0開始
+  let mut subtitles = parse_srt(SRT);
+  shift_subtitles(&mut subtitles, -1_800, None, None).unwrap();
+  assert_eq!(subtitles.len(), 1);
+  assert_eq!(subtitles[0].start_time, "00:00:00,200");
+
+  let mut subtitles = parse_srt(SRT);
+  shift_subtitles(&mut subtitles, i64::MIN, None, None).unwrap();
+  assert!(subtitles.is_empty());
+
+  let mut subtitles = parse_srt(SRT);
+  shift_subtitles(&mut subtitles, -2_500, None, None).unwrap();
+  assert_eq!(subtitles.len(), 1);
+  assert_eq!(subtitles[0].start_time, "00:00:00,000");
+  assert_eq!(subtitles[0].end_time, "00:00:00,750");
+
+  assert!(Form::<ShiftRequest>::parse("offset=-86400000").is_ok());
+  assert!(Form::<ShiftRequest>::parse("offset=86400001").is_err());
+}
+
+#[test]
+fn test_shift_subtitles_invalid_time() {
+  // 驗證與平移使用相同的時間格式
+  assert!(Form::<ShiftRequest>::parse("offset=1000&from=00:00:01,500").is_ok());
+  assert!(Form::<ShiftRequest>::parse("offset=1000&from=00:00:01.5000").is_err());
+  assert!(Form::<ShiftRequest>::parse("offset=1000&to=01:02").is_ok());
+
+  // 無法解析的字幕時間不當成0，整批拒絕
+  let mut subtitles = parse_srt(SRT);
+  subtitles[1].start_time = String::from("00:00:02,0000");
+  assert!(shift_subtitles(&mut subtitles, 1_000, None, None).is_err());
+  assert_eq!(subtitles[0].start_time, "00:00:00,000");
+  assert!(scale_subtitles(&mut subtitles, (0, 1_000), (2_000, 5_000)).is_err());
+}
+
+#[test]
+fn test_scale_subtitles() {
+  let mut subtitles = parse_srt(SRT);
+  scale_subtitles(&mut subtitles, (0, 1_000), (2_000, 5_000)).unwrap();
+  assert_eq!(subtitles[0].start_time, "00:00:01,000");
+  assert_eq!(subtitles[0].end_time, "00:00:04,000");
+  assert_eq!(subtitles[1].start_time, "00:00:05,000");
+  assert_eq!(subtitles[1].end_time, "00:00:07,500");
+
+  assert!(scale_subtitles(&mut subtitles, (2_000, 0), (1_000, 5_000)).is_err());
+}
+
+#[test]
+fn test_diff_subtitles() {
+  let old = parse_srt(SRT);
+  let mut new = parse_srt(SRT);
+  new[1].text = String::from("world");
+  new.push(Subtitle::new("bye", "00:00:04,000", "00:00:05,000"));
+
+  let changes = diff_subtitles(&old, &new);
+  assert_eq!(changes.len(), 3);
+  assert_eq!(changes[0].op, Operation::Added);
+  assert_eq!(changes[0].cue.text, "world");
+  assert_eq!(changes[1].op, Operation::Added);
+  assert_eq!(changes[1].index, 2);
+  assert_eq!(changes[2].op, Operation::Removed);
+  assert_eq!(changes[2].index, 1);
+  assert!(diff_subtitles(&old, &old).is_empty());
+}
+
+#[test]
+fn test_diff_large_subtitles() {
+  let cue = |i: usize| Subtitle::new(&format!("cue {}", i), "00:00:00,000", "00:00:01,000");
+  let old: Vec<Subtitle> = (0..3_000).map(cue).collect();
+  let mut new = old.clone();
+  new[1_500].text = String::from("edited");
+
+  // 只比較不同的段落
+  let changes = diff_subtitles(&old, &new);
+  assert_eq!(changes.len(), 2);
+  assert_eq!(changes[0].index, 1_500);
+  assert_eq!(changes[1].index, 1_500);
+
+  // 不同的段落太大時全部視為刪除後新增
+  let new: Vec<Subtitle> = (3_000..6_000).map(cue).collect();
+  let changes = diff_subtitles(&old, &new);
+  assert_eq!(changes.len(), 6_000);
+  assert_eq!(changes[0].op, Operation::Removed);
+  assert_eq!(changes[3_000].op, Operation::Added);
+}
+
+#[test]
+fn test_transcript_document() {
+  let subtitles = vec![
+    Subtitle::new("Hello", "00:00:00,000", "00:00:01,000"),
+    Subtitle::new("everyone.", "00:00:01,500", "00:00:02,000"),
+    Subtitle::new("今天", "00:01:05,000", "00:01:06,000"),
+    Subtitle::new("上課", "00:01:06,200", "00:01:07,000"),
+  ];
+
+  let paragraphs = to_paragraphs(&subtitles, 2000);
+  assert_eq!(
+    paragraphs,
+    vec![
+      (0, String::from("Hello everyone.")),
+      (65_000, String::from("今天上課"))
+    ]
+  );
+
+  let query = transcript::Query {
+    format: transcript::Format::Md,
+    pause: 2000,
+    timestamps: true,
+  };
+  assert_eq!(
+    to_transcript_document(&subtitles, &query),
+    "# Transcript\n\n## 00:00:00\n\nHello everyone.\n\n## 00:01:05\n\n今天上課\n"
+  );
+
+  let query = transcript::Query {
+    format: transcript::Format::Txt,
+    pause: 0,
+    timestamps: false,
+  };
+  assert_eq!(
+    to_transcript_document(&subtitles, &query),
+    "Hello\n\neveryone.\n\n今天\n\n上課\n"
+  );
+}
@@ -0,0 +1,36 @@
+use super::common::parse_options;
+use crate::model::transcription::*;
+use rocket::form;
+
+fn parse(query: &str) -> form::Result<'_, TranscriptionOptions> {
+  parse_options("transcription", query)
+}
+
+#[test]
+fn test_parse_transcription_options() {
+  let options = parse("").unwrap();
+  assert_eq!(options.language, None);
+  assert_eq!(options.model, WhisperModel::Base);
+  assert!(!options.translate);
+  assert_eq!(options.initial_prompt, None);
+
+  let options = parse(
+    "transcription.language=zh&transcription.model=medium&transcription.translate=true&transcription.initial_prompt=SadTalker",
+  )
+  .unwrap();
+  assert_eq!(options.language.as_deref(), Some("zh"));
+  assert_eq!(options.model, WhisperModel::Medium);
+  assert!(options.translate);
+  assert_eq!(options.initial_prompt.as_deref(), Some("SadTalker"));
+}
+
+#[test]
+fn test_parse_transcription_options_invalid() {
+  assert!(parse("transcription.language=yue").is_ok());
+  assert!(parse("transcription.language=Chinese").is_err());
+  assert!(parse("transcription.language=z").is_err());
+  assert!(parse("transcription.model=huge").is_err());
+
+  let prompt = format!("transcription.initial_prompt={}", "a".repeat(1001));
+  assert!(parse(&prompt).is_err());
+}
@@ -0,0 +1,112 @@
+use crate::{
+  api::*,
+  database,
+  model::{
+    subtitle::{Subtitle, Word},
+    translation::is_language_tag,
+  },
+  translate::*,
+};
+use dotenv::dotenv;
+use rocket::{
+  http::{ContentType, Status},
+  local::blocking::Client,
+  routes,
+};
+use std::io::Error;
+
+// 固定在原文前加上目標語言的假翻譯
+struct FakeTranslator;
+
+#[rocket::async_trait]
+impl Translator for FakeTranslator {
+  async fn translate(
+    &self,
+    texts: &[String],
+    _source: Option<&str>,
+    target: &str,
+  ) -> Result<Vec<String>, Error> {
+    Ok(
+      texts
+        .iter()
+        .map(|text| format!("[{}] {}", target, text))
+        .collect(),
+    )
+  }
+}
+
+#[test]
+fn test_is_language_tag() {
+  assert!(is_language_tag("en"));
+  assert!(is_language_tag("zh-TW"));
+  assert!(is_language_tag("zh-Hant-TW"));
+  assert!(!is_language_tag("EN"));
+  assert!(!is_language_tag("english"));
+  assert!(!is_language_tag("zh_TW"));
+  assert!(!is_language_tag("en-"));
+}
+
+#[rocket::async_test]
+async fn test_translate_subtitles() {
+  let mut subtitle = Subtitle::new("你好", "00:00:01,000", "00:00:02,500");
+  subtitle.words = vec![Word {
+    text: String::from("你好"),
+    start_time: String::from("00:00:01,000"),
+    end_time: String::from("00:00:02,000"),
+  }];
+
+  let translated = translate_subtitles(&FakeTranslator, &[subtitle], Some("zh-TW"), "en")
+    .await
+    .unwrap();
+  assert_eq!(translated[0].text, "[en] 你好");
+  assert_eq!(translated[0].start_time, "00:00:01,000");
+  assert_eq!(translated[0].end_time, "00:00:02,500");
+  assert!(translated[0].words.is_empty());
+}
+
+#[test]
+fn test_translate_subtitle_track() {
+  let code = "translate";
+  dotenv().ok();
+  database::init_db();
+  let _ = database::delete_task_by_code(code);
+  database::insert_task(code, true, false).expect("Failed to insert task");
+  let subtitles = vec![Subtitle::new("你好", "00:00:01,000", "00:00:02,500")];
+  database::update_task_subtitles(code, &subtitles, "alice", None).unwrap();
+
+  let rocket = rocket::build()
+    .mount(
+      "/",
+      routes![translate_subtitle, list_translations, get_translation],
+    )
+    .manage::<Box<dyn Translator>>(Box::new(FakeTranslator));
+  let client = Client::untracked(rocket).expect("valid rocket instance");
+
+  let response = client
+    .post(format!("/api/subtitle/{}/translations", code))
+    .header(ContentType::Form)
+    .body("target=en&source=zh-TW")
+    .dispatch();
+  assert_eq!(response.status(), Status::Ok);
+
+  let response = client
+    .get(format!("/api/subtitle/{}/translations/en", code))
+    .dispatch();
+  let track: Vec<Subtitle> = response.into_json().unwrap();
+  assert_eq!(track[0].text, "[en] 你好");
+  assert_eq!(track[0].end_time, "00:00:02,500");
+
+  let response = client
+    .get(format!("/api/subtitle/{}/translations/fr", code))
+    .dispatch();
+  assert_eq!(response.status(), Status::NotFound);
+
+  let response = client
+    .post(format!("/api/subtitle/{}/translations", code))
+    .header(ContentType::Form)
+    .body("target=English")
+    .dispatch();
+  assert_eq!(response.status(), Status::UnprocessableEntity);
+
+  database::delete_task_by_code(code).unwrap();
+}
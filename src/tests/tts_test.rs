@@ -0,0 +1,112 @@
+use crate::{
+  model::{constant::*, script::*},
+  tts::*,
+  utils::*,
+};
+use dotenv::dotenv;
+use rocket::{
+  form::Form,
+  http::{ContentType, Status},
+  local::blocking::Client,
+  post, routes, FromForm,
+};
+use std::{fs, io::Error};
+
+struct FakeTts;
+
+#[rocket::async_trait]
+impl TtsBackend for FakeTts {
+  async fn synthesize(
+    &self,
+    segments: &[ScriptSegment],
+    output_path: &str,
+  ) -> Result<Vec<(u64, u64)>, Error> {
+    fs::write(output_path, b"RIFF")?;
+    let mut cursor = 0;
+    Ok(
+      segments
+        .iter()
+        .map(|segment| {
+          let start = segment.start_time.unwrap_or(cursor);
+          cursor = start + 1_000;
+          (start, cursor)
+        })
+        .collect(),
+    )
+  }
+}
+
+#[test]
+fn test_parse_script() {
+  let segments = parse_script("Hello\n\n[00:00:05] World\n[00:00:07,500]  again ").unwrap();
+
+  assert_eq!(segments.len(), 3);
+  assert_eq!(segments[0].start_time, None);
+  assert_eq!(segments[1].text, "World");
+  assert_eq!(segments[1].start_time, Some(5_000));
+  assert_eq!(segments[2].text, "again");
+  assert_eq!(segments[2].start_time, Some(7_500));
+}
+
+#[test]
+fn test_parse_script_invalid() {
+  assert!(parse_script("").is_err());
+  assert!(parse_script("[00:00:05]").is_err());
+  assert!(parse_script("[abc] Hello").is_err());
+  assert!(parse_script("[00:00:05] Hello\n[00:00:01] World").is_err());
+}
+
+#[rocket::async_test]
+async fn test_narrate() {
+  dotenv().ok();
+  let code = "narrate";
+  create_code_dir(code).expect("Failed to create code directory");
+
+  let segments = parse_script("Hello\n[00:00:05] World").unwrap();
+  narrate(code, &FakeTts, &segments, true).await.unwrap();
+
+  assert!(get_file_path(code, AUDIO_FILE).is_ok());
+  let subs = fs::read_to_string(get_file_path(code, SUBS_FILE).unwrap()).unwrap();
+  assert!(subs.contains("00:00:00,000 --> 00:00:01,000\nHello"));
+  assert!(subs.contains("00:00:05,000 --> 00:00:06,000\nWorld"));
+
+  delete_code_dir(code).unwrap();
+}
+
+#[derive(FromForm)]
+struct Upload {
+  #[field(validate = validate_script())]
+  script: Option<String>,
+}
+
+#[post("/script", data = "<data>")]
+fn upload(data: Form<Upload>) -> String {
+  let segments = data.script.as_deref().map(parse_script);
+  segments
+    .and_then(|segments| segments.ok())
+    .map_or(0, |segments| segments.len())
+    .to_string()
+}
+
+#[test]
+fn test_upload_long_script() {
+  // 以文字檔上傳、超過Rocket預設string上限8KiB的旁白稿
+  let script = "這是一段很長的旁白稿，用來確認表單可以接受完整的講稿內容。\n".repeat(200);
+  assert!(script.len() > 8 * 1024);
+
+  let boundary = "script-boundary";
+  let body = format!(
+    "--{b}\r\nContent-Disposition: form-data; name=\"script\"\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n--{b}--\r\n",
+    script,
+    b = boundary
+  );
+  let client =
+    Client::untracked(rocket::build().mount("/", routes![upload])).expect("valid rocket instance");
+  let response = client
+    .post("/script")
+    .header(ContentType::new("multipart", "form-data").with_params(("boundary", boundary)))
+    .body(body)
+    .dispatch();
+  assert_eq!(response.status(), Status::Ok);
+  assert_eq!(response.into_string().unwrap(), "200");
+}
@@ -0,0 +1,111 @@
+use crate::{model::constant::*, utils::*};
+use dotenv::dotenv;
+
+#[test]
+fn test_build_path() {
+  dotenv().ok();
+  assert!(build_path("abc123", "").unwrap().ends_with("/tmp/abc123"));
+  assert!(build_path("abc123", "result.mp4")
+    .unwrap()
+    .ends_with("/tmp/abc123/result.mp4"));
+  assert!(build_path("abc123", "gen/chunk_0.mp4").is_ok());
+}
+
+#[test]
+fn test_build_path_traversal() {
+  dotenv().ok();
+  assert!(build_path("abc123", "../slidetalker.db3").is_err());
+  assert!(build_path("abc123", "gen/../../secret").is_err());
+  assert!(build_path("..", "result.mp4").is_err());
+  assert!(build_path("abc/123", "result.mp4").is_err());
+  assert!(build_path("", "result.mp4").is_err());
+  assert!(build_path("abc123", ".env").is_err());
+}
+
+#[test]
+fn test_delete_file_in_dir_keep_intermediate() {
+  dotenv().ok();
+  let code = "keepfiles";
+  create_code_dir(code).expect("Failed to create code directory");
+  create_dir(code, GEN_DIR).expect("Failed to create gen directory");
+  for filename in [VIDEO_FILE, AUDIO_FILE, AVATAR_VIDEO_FILE, RESULT_FILE] {
+    create_file(code, filename).expect("Failed to create file");
+  }
+
+  delete_file_in_dir(code, true).expect("Failed to delete files");
+
+  assert!(get_file_path(code, VIDEO_FILE).is_ok());
+  assert!(get_file_path(code, AVATAR_VIDEO_FILE).is_ok());
+  assert!(get_file_path(code, RESULT_FILE).is_ok());
+  assert!(get_file_path(code, AUDIO_FILE).is_ok());
+  assert!(get_file_path(code, GEN_DIR).is_err());
+
+  delete_file_in_dir(code, false).expect("Failed to delete files");
+
+  assert!(get_file_path(code, VIDEO_FILE).is_err());
+  assert!(get_file_path(code, AUDIO_FILE).is_err());
+  assert!(get_file_path(code, RESULT_FILE).is_ok());
+
+  delete_code_dir(code).expect("Failed to delete code directory");
+}
+
+#[test]
+fn test_get_video_duration() {
+  dotenv().ok();
+  let code = "duration";
+  create_code_dir(code).expect("Failed to create code directory");
+  let path = create_file(code, VIDEO_FILE).expect("Failed to create video file");
+
+  // ftyp + moov(mvhd version 0, timescale 1000, duration 12345)
+  let mut mvhd = vec![0, 0, 0, 0];
+  mvhd.extend_from_slice(&[0; 8]);
+  mvhd.extend_from_slice(&1000u32.to_be_bytes());
+  mvhd.extend_from_slice(&12345u32.to_be_bytes());
+  let mut data = Vec::new();
+  data.extend_from_slice(&16u32.to_be_bytes());
+  data.extend_from_slice(b"ftypisom\0\0\0\0");
+  data.extend_from_slice(&(16 + mvhd.len() as u32).to_be_bytes());
+  data.extend_from_slice(b"moov");
+  data.extend_from_slice(&(8 + mvhd.len() as u32).to_be_bytes());
+  data.extend_from_slice(b"mvhd");
+  data.extend_from_slice(&mvhd);
+  std::fs::write(&path, data).expect("Failed to write video file");
+
+  assert_eq!(get_video_duration(&path).unwrap(), 12345);
+
+  delete_code_dir(code).expect("Failed to delete code directory");
+}
+
+#[test]
+fn test_get_video_duration_malformed() {
+  dotenv().ok();
+  let code = "malformed";
+  create_code_dir(code).expect("Failed to create code directory");
+  let path = create_file(code, VIDEO_FILE).expect("Failed to create video file");
+
+  // mvhd version 1，duration過大
+  let mut mvhd = vec![1, 0, 0, 0];
+  mvhd.extend_from_slice(&[0; 16]);
+  mvhd.extend_from_slice(&1u32.to_be_bytes());
+  mvhd.extend_from_slice(&u64::MAX.to_be_bytes());
+  let mut data = Vec::new();
+  data.extend_from_slice(&(8 + 8 + mvhd.len() as u32).to_be_bytes());
+  data.extend_from_slice(b"moov");
+  data.extend_from_slice(&(8 + mvhd.len() as u32).to_be_bytes());
+  data.extend_from_slice(b"mvhd");
+  data.extend_from_slice(&mvhd);
+  std::fs::write(&path, &data).expect("Failed to write video file");
+  let err = get_video_duration(&path).unwrap_err();
+  assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+  // 64位元box大小超過檔案範圍
+  let mut data = Vec::new();
+  data.extend_from_slice(&1u32.to_be_bytes());
+  data.extend_from_slice(b"free");
+  data.extend_from_slice(&u64::MAX.to_be_bytes());
+  data.extend_from_slice(&[0; 16]);
+  std::fs::write(&path, &data).expect("Failed to write video file");
+  assert!(get_video_duration(&path).is_err());
+
+  delete_code_dir(code).expect("Failed to delete code directory");
+}
@@ -0,0 +1,27 @@
+use crate::version::*;
+use rocket::http::Status;
+
+#[test]
+fn test_parse_etag() {
+  assert_eq!(etag(3), "\"3\"");
+  assert_eq!(parse_etag("\"3\""), Some(3));
+  assert_eq!(parse_etag(" \"12\" "), Some(12));
+  assert_eq!(parse_etag("W/\"12\""), None);
+  assert_eq!(parse_etag("3"), None);
+  assert_eq!(parse_etag("\"abc\""), None);
+}
+
+#[test]
+fn test_if_match() {
+  assert_eq!(IfMatch(None).revision(), Err(Status::PreconditionRequired));
+  assert_eq!(IfMatch(Some(String::from("*"))).revision(), Ok(None));
+  assert_eq!(IfMatch(Some(String::from("\"2\""))).revision(), Ok(Some(2)));
+  assert_eq!(
+    IfMatch(Some(String::from("W/\"2\""))).revision(),
+    Err(Status::PreconditionFailed)
+  );
+  assert_eq!(
+    IfMatch(Some(String::from("stale"))).revision(),
+    Err(Status::PreconditionFailed)
+  );
+}
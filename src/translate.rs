@@ -0,0 +1,100 @@
+use crate::{model::subtitle::Subtitle, utils::*};
+use serde::Deserialize;
+use std::{collections::HashMap, env};
+
+#[rocket::async_trait]
+pub trait Translator: Send + Sync {
+  // 依序翻譯每句文字，回傳數量需與輸入相同
+  async fn translate(
+    &self,
+    texts: &[String],
+    source: Option<&str>,
+    target: &str,
+  ) -> Result<Vec<String>, Error>;
+}
+
+pub struct SidecarTranslator {
+  pub url: String,
+}
+
+#[derive(Deserialize)]
+struct TranslateResponse {
+  texts: Vec<String>,
+}
+
+impl SidecarTranslator {
+  pub fn new() -> SidecarTranslator {
+    SidecarTranslator {
+      url: env::var("TRANSLATE_URL")
+        .unwrap_or_else(|_| String::from("http://localhost:5000/translate")),
+    }
+  }
+}
+
+#[rocket::async_trait]
+impl Translator for SidecarTranslator {
+  async fn translate(
+    &self,
+    texts: &[String],
+    source: Option<&str>,
+    target: &str,
+  ) -> Result<Vec<String>, Error> {
+    log::info!(
+      "Translating {} texts with sidecar into {}",
+      texts.len(),
+      target
+    );
+
+    let mut map = HashMap::new();
+    map.insert("texts", serde_json::to_value(texts)?);
+    map.insert("source", serde_json::to_value(source)?);
+    map.insert("target", serde_json::Value::from(target));
+
+    let response = handle(make_request(&self.url, &map).await, "Making request")?;
+    if !response.status().is_success() {
+      return Err(Error::new(ErrorKind::Other, ""));
+    }
+
+    let response = handle(
+      response.json::<TranslateResponse>().await,
+      "Parsing translate response",
+    )?;
+    log::info!("Python translate success");
+    Ok(response.texts)
+  }
+}
+
+// 保留原字幕的時間與樣式，逐字時間不適用於譯文
+pub async fn translate_subtitles(
+  translator: &dyn Translator,
+  subtitles: &[Subtitle],
+  source: Option<&str>,
+  target: &str,
+) -> Result<Vec<Subtitle>, Error> {
+  let texts: Vec<String> = subtitles.iter().map(|sub| sub.text.clone()).collect();
+  let translated = translator.translate(&texts, source, target).await?;
+
+  if translated.len() != subtitles.len() {
+    log::error!(
+      "Translator returned {} texts for {} subtitles",
+      translated.len(),
+      subtitles.len()
+    );
+    return Err(Error::new(
+      ErrorKind::InvalidData,
+      "Subtitle count mismatch",
+    ));
+  }
+
+  Ok(
+    subtitles
+      .iter()
+      .zip(translated)
+      .map(|(sub, text)| Subtitle {
+        text,
+        words: Vec::new(),
+        ..sub.clone()
+      })
+      .collect(),
+  )
+}
@@ -0,0 +1,118 @@
+use crate::{
+  model::{constant::*, script::ScriptSegment, subtitle::Subtitle},
+  subs::*,
+  utils::*,
+};
+use serde::Deserialize;
+use std::{collections::HashMap, env, fs};
+
+#[rocket::async_trait]
+pub trait TtsBackend: Send + Sync {
+  // 將旁白稿合成為output_path的音檔，回傳每段的(開始, 結束)毫秒
+  async fn synthesize(
+    &self,
+    segments: &[ScriptSegment],
+    output_path: &str,
+  ) -> Result<Vec<(u64, u64)>, Error>;
+}
+
+pub struct SidecarTts {
+  pub url: String,
+}
+
+#[derive(Deserialize)]
+struct SegmentTiming {
+  start: u64,
+  end: u64,
+}
+
+#[derive(Deserialize)]
+struct SynthesizeResponse {
+  segments: Vec<SegmentTiming>,
+}
+
+impl SidecarTts {
+  pub fn new() -> SidecarTts {
+    SidecarTts {
+      url: env::var("TTS_URL").unwrap_or_else(|_| String::from("http://localhost:5000/tts")),
+    }
+  }
+}
+
+#[rocket::async_trait]
+impl TtsBackend for SidecarTts {
+  async fn synthesize(
+    &self,
+    segments: &[ScriptSegment],
+    output_path: &str,
+  ) -> Result<Vec<(u64, u64)>, Error> {
+    log::info!("Synthesizing {} segments with sidecar TTS", segments.len());
+
+    let mut map = HashMap::new();
+    map.insert("segments", serde_json::to_value(segments)?);
+    map.insert("output_path", serde_json::Value::from(output_path));
+
+    let response = handle(make_request(&self.url, &map).await, "Making request")?;
+    if !response.status().is_success() {
+      return Err(Error::new(ErrorKind::Other, ""));
+    }
+
+    let response = handle(
+      response.json::<SynthesizeResponse>().await,
+      "Parsing TTS response",
+    )?;
+    log::info!("Python TTS success");
+    Ok(
+      response
+        .segments
+        .iter()
+        .map(|timing| (timing.start, timing.end))
+        .collect(),
+    )
+  }
+}
+
+pub async fn narrate(
+  code: &str,
+  tts: &dyn TtsBackend,
+  segments: &[ScriptSegment],
+  subtitle: bool,
+) -> Result<(), Error> {
+  log::info!("Narrating script for code: {}", code);
+
+  let output_path = handle(create_file(code, AUDIO_FILE), "Inserting output_path")?;
+  let timings = tts.synthesize(segments, &output_path).await?;
+
+  if timings.len() != segments.len() {
+    log::error!(
+      "TTS returned {} timings for {} segments",
+      timings.len(),
+      segments.len()
+    );
+    return Err(Error::new(ErrorKind::InvalidData, "Segment count mismatch"));
+  }
+
+  // 以旁白稿作為字幕
+  if subtitle {
+    let subtitles: Vec<Subtitle> = segments
+      .iter()
+      .zip(timings)
+      .map(|(segment, (start, end))| {
+        Subtitle::new(
+          &segment.text,
+          &format_time(start, ','),
+          &format_time(end, ','),
+        )
+      })
+      .collect();
+
+    let subs_path = handle(create_file(code, SUBS_FILE), "Creating subtitles file")?;
+    handle(
+      fs::write(&subs_path, to_srt(&subtitles)),
+      &format!("Writing file '{}'", subs_path),
+    )?;
+  }
+
+  log::info!("Narration completed for code: {}", code);
+  Ok(())
+}
@@ -8,6 +8,7 @@ use std::{
   collections::HashMap,
   env,
   fs::{self, File},
+  io::{Read, Seek, SeekFrom},
   path::{Path, PathBuf},
   time::{SystemTime, UNIX_EPOCH},
 };
@@ -33,11 +34,32 @@ where
 //   }
 // }
 
+pub fn build_path(code: &str, filename: &str) -> Result<String, Error> {
+  // 只允許英數字的code，與不含路徑跳脫的檔名
+  let valid_code = !code.is_empty() && code.chars().all(|c| c.is_ascii_alphanumeric());
+  let valid_filename = filename.split('/').all(|part| {
+    !part.starts_with('.')
+      && part
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+  }) && !filename.contains("//");
+
+  if !valid_code || !valid_filename {
+    log::warn!("Rejected path '{}' for code: {}", filename, code);
+    return Err(Error::new(ErrorKind::InvalidInput, "Invalid path"));
+  }
+
+  let root = env::var("ROOT").expect("Failed to get root path");
+  match filename {
+    "" => Ok(format!("{}/tmp/{}", root, code)),
+    _ => Ok(format!("{}/tmp/{}/{}", root, code, filename)),
+  }
+}
+
 pub fn get_file_path(code: &str, filename: &str) -> Result<String, Error> {
   log::debug!("Getting path of file '{}' for code: {}", filename, code);
 
-  let root = env::var("ROOT").expect("Failed to get root path");
-  let path = format!("{}/tmp/{}/{}", root, code, filename);
+  let path = build_path(code, filename)?;
   log::debug!("path={}", path);
 
   let file_path = Path::new(path.as_str());
@@ -49,11 +71,18 @@ pub fn get_file_path(code: &str, filename: &str) -> Result<String, Error> {
   Err(Error::new(ErrorKind::Other, ""))
 }
 
+// 旁白依上傳的格式有不同的副檔名
+pub fn get_narration_path(code: &str) -> Result<String, Error> {
+  NARRATION_FILES
+    .iter()
+    .find_map(|filename| get_file_path(code, filename).ok())
+    .ok_or_else(|| Error::new(ErrorKind::NotFound, "Narration not found"))
+}
+
 pub fn create_file(code: &str, filename: &str) -> Result<String, Error> {
   log::debug!("Creating file '{}' for code: {}", filename, code);
 
-  let root = env::var("ROOT").expect("Failed to get root path");
-  let path = format!("{}/tmp/{}/{}", root, code, filename);
+  let path = build_path(code, filename)?;
   log::debug!("path={}", path);
 
   handle(File::create(&path), &format!("Creating file '{}'", path))?;
@@ -64,8 +93,7 @@ pub fn create_file(code: &str, filename: &str) -> Result<String, Error> {
 pub fn create_dir(code: &str, dirname: &str) -> Result<String, Error> {
   log::debug!("Creating directory '{}' for code: {}", dirname, code);
 
-  let root = env::var("ROOT").expect("Failed to get root path");
-  let path = format!("{}/tmp/{}/{}", root, code, dirname);
+  let path = build_path(code, dirname)?;
   log::debug!("path={}", path);
 
   handle(
@@ -80,8 +108,7 @@ pub fn create_dir(code: &str, dirname: &str) -> Result<String, Error> {
 pub fn create_code_dir(code: &str) -> Result<String, Error> {
   log::debug!("Creating code directory for code: {}", code);
 
-  let root = env::var("ROOT").expect("Failed to get root path");
-  let path = format!("{}/tmp/{}", root, code);
+  let path = build_path(code, "")?;
   log::debug!("path={}", path);
 
   handle(
@@ -93,17 +120,30 @@ pub fn create_code_dir(code: &str) -> Result<String, Error> {
   Ok(path)
 }
 
-pub fn delete_file_in_dir(code: &str) -> Result<(), Error> {
+pub fn delete_file_in_dir(code: &str, keep_intermediate: bool) -> Result<(), Error> {
   // 刪除除了files_to_keep以外的檔案
   log::info!("Deleting files in directory by code: {}", code);
 
-  let gen = handle(get_file_path(code, "gen"), "Getting file path")?;
-  handle(
-    fs::remove_dir_all(&gen),
-    &format!("Removing directory '{}'", gen),
-  )?;
+  if let Ok(gen) = get_file_path(code, GEN_DIR) {
+    handle(
+      fs::remove_dir_all(&gen),
+      &format!("Removing directory '{}'", gen),
+    )?;
+  }
 
-  let files_to_keep = [RESULT_FILE, RESULT_WITH_SUBS_FILE];
+  let mut files_to_keep = vec![
+    RESULT_FILE,
+    RESULT_WITH_SUBS_FILE,
+    SUBS_FILE,
+    WORDS_FILE,
+    CHAPTERS_FILE,
+    BG_AVATAR_FILE,
+  ];
+  // 保留模式下留下重新合成所需的檔案
+  if keep_intermediate {
+    files_to_keep.extend([VIDEO_FILE, AVATAR_VIDEO_FILE, AUDIO_FILE, SCRIPT_FILE]);
+    files_to_keep.extend(NARRATION_FILES);
+  }
   let folder_path = get_file_path(code, "")?;
 
   let dir = handle(
@@ -148,8 +188,7 @@ pub fn delete_file_in_dir(code: &str) -> Result<(), Error> {
 pub fn delete_code_dir(code: &str) -> Result<(), Error> {
   log::info!("Deleting directory for code: {}", code);
 
-  let root = env::var("ROOT").expect("Failed to get root path");
-  let path = format!("{}/tmp/{}", root, code);
+  let path = build_path(code, "")?;
   log::debug!("path={}", path);
 
   let path_buf = PathBuf::from(&path);
@@ -166,6 +205,83 @@ pub fn delete_code_dir(code: &str) -> Result<(), Error> {
   Ok(())
 }
 
+// 讀取MP4/MOV的mvhd取得影片長度（毫秒）
+pub fn get_video_duration(path: &str) -> Result<u64, Error> {
+  log::debug!("Getting video duration of '{}'", path);
+
+  let mut file = handle(File::open(path), &format!("Opening file '{}'", path))?;
+  let len = handle(file.metadata(), &format!("Reading metadata of '{}'", path))?.len();
+
+  let (moov_start, moov_end) = find_box(&mut file, 0, len, b"moov")?;
+  let (mvhd_start, _) = find_box(&mut file, moov_start, moov_end, b"mvhd")?;
+
+  handle(file.seek(SeekFrom::Start(mvhd_start)), "Seeking file")?;
+  let mut version = [0; 4];
+  handle(file.read_exact(&mut version), "Reading mvhd")?;
+
+  let (timescale, duration) = match version[0] {
+    1 => {
+      let mut buf = [0; 28];
+      handle(file.read_exact(&mut buf), "Reading mvhd")?;
+      let timescale = u32::from_be_bytes(buf[16..20].try_into().unwrap()) as u64;
+      let duration = u64::from_be_bytes(buf[20..28].try_into().unwrap());
+      (timescale, duration)
+    }
+    _ => {
+      let mut buf = [0; 16];
+      handle(file.read_exact(&mut buf), "Reading mvhd")?;
+      let timescale = u32::from_be_bytes(buf[8..12].try_into().unwrap()) as u64;
+      let duration = u32::from_be_bytes(buf[12..16].try_into().unwrap()) as u64;
+      (timescale, duration)
+    }
+  };
+
+  if timescale == 0 {
+    return Err(Error::new(ErrorKind::InvalidData, "Invalid timescale"));
+  }
+  log::debug!("timescale={}, duration={}", timescale, duration);
+  // 長度來自上傳的檔案，以u128計算避免溢位
+  u64::try_from(duration as u128 * 1000 / timescale as u128)
+    .map_err(|_| Error::new(ErrorKind::InvalidData, "Invalid duration"))
+}
+
+// 在[start, end)範圍內尋找box，回傳其內容的範圍
+fn find_box(file: &mut File, start: u64, end: u64, name: &[u8; 4]) -> Result<(u64, u64), Error> {
+  let invalid = || Error::new(ErrorKind::InvalidData, "Invalid box size");
+  let mut offset = start;
+
+  while offset.checked_add(8).ok_or_else(invalid)? <= end {
+    handle(file.seek(SeekFrom::Start(offset)), "Seeking file")?;
+    let mut header = [0; 8];
+    handle(file.read_exact(&mut header), "Reading box header")?;
+
+    let mut size = u32::from_be_bytes(header[0..4].try_into().unwrap()) as u64;
+    let mut header_len = 8;
+    if size == 1 {
+      let mut large = [0; 8];
+      handle(file.read_exact(&mut large), "Reading box size")?;
+      size = u64::from_be_bytes(large);
+      header_len = 16;
+    } else if size == 0 {
+      size = end - offset;
+    }
+    if size < header_len {
+      break;
+    }
+
+    let box_end = offset.checked_add(size).ok_or_else(invalid)?;
+    if &header[4..8] == name {
+      return Ok((offset + header_len, box_end.min(end)));
+    }
+    offset = box_end;
+  }
+
+  Err(Error::new(
+    ErrorKind::NotFound,
+    format!("Box '{}' not found", String::from_utf8_lossy(name)),
+  ))
+}
+
 pub fn generate_rand_code() -> String {
   log::info!("Generating random code");
 
@@ -222,6 +338,10 @@ pub fn get_datetime() -> NaiveDateTime {
   Local::now().naive_local()
 }
 
+pub fn get_timestamp() -> i64 {
+  Local::now().timestamp()
+}
+
 pub fn get_tomorrow_midnight() -> NaiveDateTime {
   let now = Local::now().date_naive().and_hms_opt(0, 0, 0).unwrap();
   let get_tomorrow_midnight = now + Duration::days(1);
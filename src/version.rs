@@ -0,0 +1,65 @@
+use rocket::{
+  http::Status,
+  request::{FromRequest, Outcome, Request},
+  response::{self, Responder},
+};
+
+// 字幕以最新的版本號作為ETag
+pub fn etag(revision: u32) -> String {
+  format!("\"{}\"", revision)
+}
+
+// If-Match使用強比對，弱ETag(W/"n")一律不相符
+pub fn parse_etag(value: &str) -> Option<u32> {
+  value
+    .trim()
+    .strip_prefix('"')?
+    .strip_suffix('"')?
+    .parse()
+    .ok()
+}
+
+pub struct IfMatch(pub Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfMatch {
+  type Error = ();
+
+  async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+    Outcome::Success(IfMatch(req.headers().get_one("If-Match").map(String::from)))
+  }
+}
+
+impl IfMatch {
+  // 寫入時必須帶If-Match，"*"表示不檢查版本
+  pub fn revision(&self) -> Result<Option<u32>, Status> {
+    match self.0.as_deref().map(str::trim) {
+      None => {
+        log::warn!("If-Match header required");
+        Err(Status::PreconditionRequired)
+      }
+      Some("*") => Ok(None),
+      Some(value) => match parse_etag(value) {
+        Some(revision) => Ok(Some(revision)),
+        None => {
+          log::warn!("Invalid If-Match header: {}", value);
+          Err(Status::PreconditionFailed)
+        }
+      },
+    }
+  }
+}
+
+// 在回應中加上ETag
+pub struct Tagged<R> {
+  pub inner: R,
+  pub revision: u32,
+}
+
+impl<'r, 'o: 'r, R: Responder<'r, 'o>> Responder<'r, 'o> for Tagged<R> {
+  fn respond_to(self, req: &'r Request<'_>) -> response::Result<'o> {
+    let mut response = self.inner.respond_to(req)?;
+    response.set_raw_header("ETag", etag(self.revision));
+    Ok(response)
+  }
+}
@@ -1,7 +1,9 @@
 use crate::{
   controller::*,
   database,
+  formatter::*,
   model::{
+    avatar::BackgroundMode,
     constant::*,
     task::{
       Status::{Fail, Finish},
@@ -9,14 +11,17 @@ use crate::{
     },
     worker,
   },
+  subs::record_original_revision,
+  tts::*,
   utils::*,
 };
-use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::sync::mpsc::{Receiver, Sender};
 
 pub async fn start_gen_video_worker(
   mut rx: Receiver<worker::GenVideoRequest>,
   tx: Sender<worker::MergeSubsRequest>,
+  tts: Arc<dyn TtsBackend>,
 ) {
   log::info!("Starting video generation worker!");
 
@@ -24,11 +29,21 @@ pub async fn start_gen_video_worker(
     log::info!("Received a request to gen video for code: {}", request.code);
 
     let code = &request.code;
-    let x = request.x;
-    let y = request.y;
-    let shape = request.shape;
+    let layout = request.layout;
     let subtitle = request.subtitle;
     let remove_bg = request.remove_bg;
+    let background = request.background;
+
+    // 預覽任務轉為完整生成時先還原原始檔案
+    if request.preview.is_none() {
+      if let Err(_) = handle(
+        promote_preview(code),
+        &format!("Promoting preview for code: {}", code),
+      ) {
+        let _ = result(code, false);
+        continue;
+      }
+    }
 
     // 移除背景
     if remove_bg {
@@ -41,19 +56,79 @@ pub async fn start_gen_video_worker(
       }
     }
 
-    // 提取音檔
-    if let Err(_) = handle(
-      mp4_to_wav(code).await,
-      &format!("Running mp4_to_wav for code: {}", code),
-    ) {
+    // 替換背景
+    let image = match (remove_bg, background.mode) {
+      (false, _) => AVATAR_FILE,
+      (true, BackgroundMode::Transparent) => DEBG_AVATAR_FILE,
+      (true, _) => {
+        if let Err(_) = handle(
+          replace_background(code, &background).await,
+          &format!("Replacing background for code: {}", code),
+        ) {
+          let _ = result(code, false);
+          continue;
+        }
+        BG_AVATAR_FILE
+      }
+    };
+
+    // 提取音檔，有上傳旁白時改用旁白
+    let extracted = match (&request.script, request.narration) {
+      (Some(segments), _) => handle(
+        narrate(code, tts.as_ref(), segments, subtitle).await,
+        &format!("Running narrate for code: {}", code),
+      ),
+      (None, true) => handle(
+        convert_audio_to_wav(code).await,
+        &format!("Running convert_audio_to_wav for code: {}", code),
+      ),
+      (None, false) => handle(
+        mp4_to_wav(code).await,
+        &format!("Running mp4_to_wav for code: {}", code),
+      ),
+    };
+    if let Err(_) = extracted {
       let _ = result(code, false);
       continue;
     }
 
-    // 生成字幕
-    if subtitle {
+    // 音訊後處理
+    if request.audio.is_enabled() {
+      if let Err(_) = handle(
+        process_audio(code, &request.audio).await,
+        &format!("Running process_audio for code: {}", code),
+      ) {
+        let _ = result(code, false);
+        continue;
+      }
+    }
+
+    // 以投影片與旁白產生影片
+    if let Some(slideshow) = &request.slideshow {
       if let Err(_) = handle(
-        gen_subtitle(code).await,
+        build_slideshow(code, slideshow).await,
+        &format!("Running build_slideshow for code: {}", code),
+      ) {
+        let _ = result(code, false);
+        continue;
+      }
+    }
+
+    // 預覽模式只保留前幾秒
+    if let Some(seconds) = request.preview {
+      if let Err(_) = handle(
+        trim_preview(code, seconds).await,
+        &format!("Running trim_preview for code: {}", code),
+      ) {
+        let _ = result(code, false);
+        continue;
+      }
+    }
+
+    // 生成字幕，使用旁白稿時字幕已由旁白稿產生
+    if subtitle && request.script.is_none() {
+      if let Err(_) = handle(
+        gen_subtitle(code, &request.transcription).await,
         &format!("Running gen_subtitle for code: {}", code),
       ) {
         let _ = result(code, false);
@@ -61,9 +136,36 @@ pub async fn start_gen_video_worker(
       }
     }
 
+    // 字幕斷行
+    if subtitle && request.subtitle_format.enabled {
+      if let Err(_) = handle(
+        format_subtitle_file(code, &request.subtitle_format),
+        &format!("Running format_subtitle_file for code: {}", code),
+      ) {
+        let _ = result(code, false);
+        continue;
+      }
+    }
+
+    // 保存原始字幕為第0版
+    if subtitle {
+      let _ = handle(
+        record_original_revision(code),
+        &format!("Recording original revision for code: {}", code),
+      );
+    }
+
+    // 依投影片切換產生章節，失敗時不影響影片生成
+    if request.chapters.enabled {
+      let _ = handle(
+        detect_chapters(code, &request.chapters).await,
+        &format!("Running detect_chapters for code: {}", code),
+      );
+    }
+
     // 生成頭像模擬影片
     if let Err(_) = handle(
-      run_gen_video_python(code, remove_bg).await,
+      run_gen_video_python(code, image).await,
       &format!("Running run_gen_video_python for code: {}", code),
     ) {
       let _ = result(code, false);
@@ -81,7 +183,7 @@ pub async fn start_gen_video_worker(
 
     // 合成原影片與生成的頭像
     if let Err(_) = handle(
-      merge_video_and_avatar_video(code, x, y, &shape).await,
+      merge_video_and_avatar_video(code, &layout).await,
       &format!("Running merge_video_and_avatar_video for code: {}", code),
     ) {
       let _ = result(code, false);
@@ -97,63 +199,18 @@ pub async fn start_gen_video_worker(
       continue;
     };
 
+    // 合成影片與字幕
     if subtitle {
-      // let subtitles = handle(
-      //   database::get_subtitles(code),
-      //   &format!("Getting subtitles for code: {}", code),
-      // )?;
-
-      let video_path: String;
-      let output_path: String;
-      let subtitles_path: String;
-
-      if let Ok(p) = handle(get_file_path(code, SUBS_FILE), "Inserting subtitles_path") {
-        subtitles_path = p;
-      } else {
-        let _ = result(code, false);
-        continue;
-      };
-
-      if let Ok(p) = handle(get_file_path(code, RESULT_FILE), "Inserting video_path") {
-        video_path = p;
-      } else {
-        let _ = result(code, false);
-        continue;
-      };
-
-      if let Ok(p) = handle(
-        create_file(code, RESULT_WITH_SUBS_FILE),
-        "Inserting output_path",
+      if let Err(_) = handle(
+        merge_video_and_subtitle_file(code).await,
+        &format!("Running merge_video_and_subtitle_file for code: {}", code),
       ) {
-        output_path = p;
-      } else {
-        let _ = result(code, false);
-        continue;
-      };
-
-      let mut data = HashMap::new();
-
-      data.insert("subtitle_path", subtitles_path);
-      data.insert("video_path", video_path);
-      data.insert("output_path", output_path);
-
-      let response = handle(
-        make_request("http://localhost:5000/merge_video_and_subtitle", &data).await,
-        "Making request",
-      )
-      .unwrap();
-
-      if response.status().is_success() {
-        log::info!("Python merge video and subtitle subtitle success");
-        let _ = result(code, true);
-      } else {
         let _ = result(code, false);
         continue;
       }
-    } else {
-      let _ = result(code, true);
     }
 
+    let _ = result(code, true);
     log::info!("Video generation completed for code: {}", code);
   }
 }
@@ -181,17 +238,30 @@ pub async fn start_merge_subs_worker(mut rx: Receiver<worker::MergeSubsRequest>)
       }
     }
 
-    match (task.subs_status, task.video_status) {
-      (Finish, Finish) => {
-        if let Err(_) = handle(
-          merge_video_and_subtitle(code).await,
-          &format!("Running merge_video_and_subtitle for code: {}", code),
-        ) {
-          let _ = result(code, false);
-          continue;
-        }
-      }
-      (_, _) => continue,
+    // 影片尚未合成完成時，生成結束後會再合成字幕與章節
+    if !matches!(task.video_status, Finish) {
+      continue;
+    }
+
+    // 沒有編輯過字幕時改用Whisper產生的srt，都沒有則只寫入章節
+    let merged = match subtitle_source(code) {
+      Ok(SubtitleSource::Database) => handle(
+        merge_video_and_subtitle(code).await,
+        &format!("Running merge_video_and_subtitle for code: {}", code),
+      ),
+      Ok(SubtitleSource::File) => handle(
+        merge_video_and_subtitle_file(code).await,
+        &format!("Running merge_video_and_subtitle_file for code: {}", code),
+      ),
+      Ok(SubtitleSource::None) => handle(
+        write_chapters(code).await,
+        &format!("Running write_chapters for code: {}", code),
+      ),
+      Err(e) => Err(e),
+    };
+    if merged.is_err() {
+      let _ = result(code, false);
+      continue;
     }
 
     let _ = result(code, true);
@@ -199,6 +269,50 @@ pub async fn start_merge_subs_worker(mut rx: Receiver<worker::MergeSubsRequest>)
   }
 }
 
+pub async fn start_rerender_worker(mut rx: Receiver<worker::RerenderRequest>) {
+  log::info!("Starting rerender worker!");
+
+  while let Some(request) = rx.recv().await {
+    log::info!("Received a request to rerender for code: {}", request.code);
+
+    let code = &request.code;
+
+    // 以保留的頭像影片重新合成
+    if let Err(_) = handle(
+      merge_video_and_avatar_video(code, &request.layout).await,
+      &format!("Running merge_video_and_avatar_video for code: {}", code),
+    ) {
+      let _ = result(code, false);
+      continue;
+    }
+
+    if let Err(_) = handle(
+      database::update_video_status(code, Finish),
+      &format!("Updating video status to 'Finish' for code: {}", code),
+    ) {
+      let _ = result(code, false);
+      continue;
+    };
+
+    // 有編輯過的字幕時使用資料庫中的字幕，否則使用Whisper產生的srt
+    let merged = match subtitle_source(code) {
+      Ok(SubtitleSource::Database) => handle(
+        merge_video_and_subtitle(code).await,
+        &format!("Running merge_video_and_subtitle for code: {}", code),
+      ),
+      Ok(SubtitleSource::File) => handle(
+        merge_video_and_subtitle_file(code).await,
+        &format!("Running merge_video_and_subtitle_file for code: {}", code),
+      ),
+      Ok(SubtitleSource::None) => Ok(()),
+      Err(e) => Err(e),
+    };
+
+    let _ = result(code, merged.is_ok());
+    log::info!("Rerender completed for code: {}", code);
+  }
+}
+
 fn result(code: &str, success: bool) -> Result<(), Error> {
   if success {
     // 設定任務狀態為'Finish'
@@ -230,11 +344,21 @@ fn result(code: &str, success: bool) -> Result<(), Error> {
     }
   }
 
-  // 刪除不必要檔案
-  handle(
-    delete_file_in_dir(code),
-    &format!("Deleting file in directoey '{}'", code),
+  // 刪除不必要檔案，預覽任務保留所有檔案以便轉為完整生成
+  let task = handle(
+    database::get_task_info(code),
+    &format!("Getting task info for code: {}", code),
+  )?;
+  let preview = handle(
+    database::get_render_request(code),
+    &format!("Getting render request for code: {}", code),
   )?;
+  if preview.is_none() {
+    handle(
+      delete_file_in_dir(code, task.keep_files),
+      &format!("Deleting file in directoey '{}'", code),
+    )?;
+  }
 
   if success {
     log::info!("Task of code: {} completed", code);